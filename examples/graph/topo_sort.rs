@@ -25,7 +25,9 @@ fn main() {
 
     access_map.add_access_matrix(AccessMatrix(vec![vec![1]]));
     access_map.add_access_matrix(AccessMatrix(vec![vec![1]]));
+    access_map.add_access_matrix(AccessMatrix(vec![vec![0]]));
 
+    access_map.add_access_offset(AccessOffset(vec![0]));
     access_map.add_access_offset(AccessOffset(vec![0]));
     access_map.add_access_offset(AccessOffset(vec![0]));
 
@@ -75,7 +77,7 @@ fn main() {
 
     subgraph.connect();
 
-    let sort_nodes = subgraph.topo_sort();
+    let sort_nodes = subgraph.topo_sort().unwrap();
 
     for node in sort_nodes {
         println!("Node: {:?}", node.borrow().get_node_name());
@@ -18,9 +18,12 @@ fn main() {
     let mut access_map = AccessMap::new(1, vec![1]);
     access_map.add_iter_var(iter_var);
 
+    // Access maps of A, B and C.
     access_map.add_access_matrix(AccessMatrix(vec![vec![1]]));
     access_map.add_access_matrix(AccessMatrix(vec![vec![1]]));
+    access_map.add_access_matrix(AccessMatrix(vec![vec![0]]));
 
+    access_map.add_access_offset(AccessOffset(vec![0]));
     access_map.add_access_offset(AccessOffset(vec![0]));
     access_map.add_access_offset(AccessOffset(vec![0]));
 
@@ -0,0 +1,400 @@
+use std::{cell::RefCell, rc::Rc};
+
+use thriller_core::{
+    AccessMap, AccessMatrix, AccessOffset, AllocateVar, AttachedEdge, GraphPass, IterationBound,
+    IterationVar, ReductionAnalysis, RegularVar, Task, ThreadScope, ThrillerBlock, ThrillerEngine,
+    ThrillerError, ThrillerGraph, ThrillerNode, ThrillerNodeInner, Var,
+};
+
+use thriller_utils::BufBuilder;
+
+mod common;
+use common::{fixed_ivar, setup};
+
+#[test]
+fn test_symbolic_loop_bounds() {
+    let _guard = setup();
+
+    let m = fixed_ivar("m", 4);
+    let k = Rc::new(IterationVar::new(
+        "k",
+        (
+            IterationBound::Fixed(0),
+            IterationBound::Var(RegularVar::new("K".to_string())),
+        ),
+    ));
+
+    let subgraph = Rc::new(RefCell::new(ThrillerGraph::new()));
+    let block = ThrillerBlock::new(vec![], vec![], subgraph, vec![m, k]);
+
+    let code = block.emit().unwrap();
+    assert!(code.contains("for(int m = 0; m < 4; ++m){"));
+    assert!(code.contains("for(int k = 0; k < K; ++k){"));
+
+    let engine = ThrillerEngine::new(block);
+    let code = engine.emit_dataflow("symbolic").unwrap();
+    assert!(code.contains("__global__ void symbolic(int K)"));
+
+    // An expression bound is emitted as is, its variables become parameters.
+    let bound = |name: &str| {
+        Rc::new(IterationVar::new(
+            "k",
+            (
+                IterationBound::Fixed(0),
+                IterationBound::Var(RegularVar::new(name.to_string())),
+            ),
+        ))
+    };
+    let block = |k: Rc<IterationVar>| {
+        let subgraph = Rc::new(RefCell::new(ThrillerGraph::new()));
+        ThrillerBlock::new(vec![], vec![], subgraph, vec![k])
+    };
+
+    let engine = ThrillerEngine::new(block(bound("(K + TILE_K - 1) / TILE_K")));
+    let code = engine.emit_dataflow("symbolic").unwrap();
+    assert!(code.contains("for(int k = 0; k < (K + TILE_K - 1) / TILE_K; ++k){"));
+    assert!(code.contains("__global__ void symbolic(int K, int TILE_K)"));
+
+    // Anything but an arithmetic expression is rejected.
+    let engine = ThrillerEngine::new(block(bound("K.size()")));
+    assert!(matches!(
+        engine.emit_dataflow("symbolic"),
+        Err(ThrillerError::InvalidLoopBound(_))
+    ));
+}
+
+#[test]
+fn test_store_access() {
+    let _guard = setup();
+
+    let n = fixed_ivar("n", 4);
+    let k = fixed_ivar("k", 8);
+
+    let g_a = Rc::new(BufBuilder::row_major_global_tile("gA", &[256, 256]));
+    let r_a = Rc::new(BufBuilder::row_major_reg_tile("rA", &[64, 32]));
+    let r_c = Rc::new(BufBuilder::row_major_reg_tile("rC", &[64, 64]));
+    let g_c = Rc::new(BufBuilder::row_major_global_tile("gC", &[256, 256]));
+
+    // gA(k) -> rA
+    let mut load_map = AccessMap::new(1, vec![1]);
+    load_map.add_iter_vars(vec![k.clone()]);
+    load_map.add_access_matrixs(vec![
+        AccessMatrix(vec![vec![1]]),
+        AccessMatrix(vec![vec![0]]),
+    ]);
+    load_map.add_access_offsets(vec![AccessOffset(vec![0]), AccessOffset(vec![0])]);
+
+    // rC -> gC(n), written back once per `n` iteration.
+    let mut store_map = AccessMap::new(1, vec![1]);
+    store_map.add_iter_vars(vec![n.clone()]);
+    store_map.add_access_matrixs(vec![
+        AccessMatrix(vec![vec![0]]),
+        AccessMatrix(vec![vec![1]]),
+    ]);
+    store_map.add_access_offsets(vec![AccessOffset(vec![0]), AccessOffset(vec![0])]);
+
+    let load = Rc::new(AttachedEdge::new(
+        g_a.clone(),
+        r_a.clone(),
+        Rc::new(load_map),
+    ));
+    let store = Rc::new(AttachedEdge::new(
+        r_c.clone(),
+        g_c.clone(),
+        Rc::new(store_map),
+    ));
+
+    let subgraph = Rc::new(RefCell::new(ThrillerGraph::new()));
+    let block = ThrillerBlock::new(vec![load], vec![store], subgraph, vec![n, k]);
+
+    let code = block.emit().unwrap();
+    let expected = format!(
+        "for(int n = 0; n < 4; ++n){{\n\
+         \x20   for(int k = 0; k < 8; ++k){{\n\
+         \x20       loader_tile_g2r_{ga}_to_{ra}(gA(1 * k), rA(0 * k));\n\
+         \x20   }}\n\
+         \x20   storer_tile_r2g_{rc}_to_{gc}(rC(0 * n), gC(1 * n));\n\
+         }}\n\
+         __syncthreads();\n",
+        ga = g_a.get_id(),
+        ra = r_a.get_id(),
+        rc = r_c.get_id(),
+        gc = g_c.get_id()
+    );
+    assert_eq!(code, expected);
+}
+
+#[test]
+fn test_reduction_store_placement() {
+    let _guard = setup();
+
+    let k = fixed_ivar("k", 8);
+    let m = fixed_ivar("m", 4);
+    let n = fixed_ivar("n", 2);
+
+    let r_c = Rc::new(BufBuilder::row_major_reg_tile("rC", &[64, 64]));
+    let g_c = Rc::new(BufBuilder::row_major_global_tile("gC", &[256, 256]));
+
+    let store = |ivars: Vec<Rc<IterationVar>>, target: Vec<Vec<usize>>| {
+        let columns = ivars.len();
+        let rows = target.len();
+        let mut map = AccessMap::new(columns, vec![rows]);
+        map.add_iter_vars(ivars);
+        map.add_access_matrixs(vec![
+            AccessMatrix(vec![vec![0; columns]; rows]),
+            AccessMatrix(target),
+        ]);
+        map.add_access_offsets(vec![
+            AccessOffset(vec![0; rows]),
+            AccessOffset(vec![0; rows]),
+        ]);
+        Rc::new(AttachedEdge::new(r_c.clone(), g_c.clone(), Rc::new(map)))
+    };
+
+    let outputs = vec![
+        // gC(m, n): `k` is reduced, and it is the outermost loop.
+        store(
+            vec![k.clone(), m.clone(), n.clone()],
+            vec![vec![0, 1, 0], vec![0, 0, 1]],
+        ),
+        // gC(k): `m` and `n` are reduced.
+        store(vec![k.clone()], vec![vec![1]]),
+        // gC(k, m, n): nothing is reduced.
+        store(
+            vec![k.clone(), m.clone(), n.clone()],
+            vec![vec![1, 0, 0], vec![0, 1, 0], vec![0, 0, 1]],
+        ),
+    ];
+
+    let subgraph = Rc::new(RefCell::new(ThrillerGraph::new()));
+    let block = ThrillerBlock::new(vec![], outputs, subgraph, vec![k, m, n]);
+    let analysis = ReductionAnalysis::new(&block);
+
+    let names = |index: usize| {
+        analysis
+            .get_reduction_ivars(index)
+            .iter()
+            .map(|ivar| ivar.get_name().clone())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(names(0), vec!["k"]);
+    assert_eq!(analysis.get_store_depth(0), 0);
+    assert_eq!(names(1), vec!["m", "n"]);
+    assert_eq!(analysis.get_store_depth(1), 1);
+    assert!(names(2).is_empty());
+    assert_eq!(analysis.get_store_depth(2), 3);
+}
+
+#[test]
+fn test_pipelined_loads() {
+    let _guard = setup();
+
+    let m = fixed_ivar("m", 4);
+    let k = fixed_ivar("k", 8);
+
+    let g_a = Rc::new(BufBuilder::row_major_global_tile("gA", &[256, 256]));
+    let s_a = Rc::new(BufBuilder::row_major_shared_tile("sA", &[64, 32]));
+    let r_a = Rc::new(BufBuilder::row_major_reg_tile("rA", &[64, 32]));
+
+    let identity = |rows: Vec<Vec<usize>>| {
+        let mut map = AccessMap::new(2, vec![rows.len()]);
+        map.add_iter_vars(vec![m.clone(), k.clone()]);
+        map.add_access_matrixs(vec![AccessMatrix(rows), AccessMatrix(vec![vec![0, 0]])]);
+        Rc::new(map)
+    };
+
+    // gA(m, k) -> sA -> rA
+    let g2s = Rc::new(AttachedEdge::new(
+        g_a.clone(),
+        s_a.clone(),
+        identity(vec![vec![1, 0], vec![0, 1]]),
+    ));
+    let s2r = Rc::new(AttachedEdge::new(
+        s_a.clone(),
+        r_a.clone(),
+        identity(vec![vec![0, 0]]),
+    ));
+
+    let subgraph = Rc::new(RefCell::new(ThrillerGraph::new()));
+    let mut block = ThrillerBlock::new(vec![g2s, s2r], vec![], subgraph, vec![m, k]);
+    block.set_stages(3);
+
+    // Two stages are filled before the loop, then iteration `k + 2` is
+    // loaded while computing iteration `k`.
+    let code = block.emit().unwrap();
+    let expected = format!(
+        "for(int m = 0; m < 4; ++m){{\n\
+         \x20   loader_tile_g2s_{ga}_to_{sa}(gA(1 * m + 0 * 0, 0 * m + 1 * 0), sA[0](0 * m + 0 * 0));\n\
+         \x20   cute::cp_async_fence();\n\
+         \x20   loader_tile_g2s_{ga}_to_{sa}(gA(1 * m + 0 * 1, 0 * m + 1 * 1), sA[1](0 * m + 0 * 1));\n\
+         \x20   cute::cp_async_fence();\n\
+         \x20   for(int k = 0; k < 8; ++k){{\n\
+         \x20       if(k + 2 < 8){{\n\
+         \x20           loader_tile_g2s_{ga}_to_{sa}(gA(1 * m + 0 * (k + 2), 0 * m + 1 * (k + 2)), \
+         sA[(k + 2) % 3](0 * m + 0 * (k + 2)));\n\
+         \x20       }}\n\
+         \x20       cute::cp_async_fence();\n\
+         \x20       cute::cp_async_wait<2>();\n\
+         \x20       __syncthreads();\n\
+         \x20       loader_tile_s2r_{sa}_to_{ra}(sA[k % 3](0 * m + 0 * k), rA(0 * m + 0 * k));\n\
+         \x20       __syncthreads();\n\
+         \x20   }}\n\
+         \x20   cute::cp_async_wait<0>();\n\
+         }}\n\
+         __syncthreads();\n",
+        ga = g_a.get_id(),
+        sa = s_a.get_id(),
+        ra = r_a.get_id()
+    );
+    assert_eq!(code, expected);
+
+    // The shared tile is allocated once per stage on the planned memory.
+    let mut graph = ThrillerGraph::new();
+    graph.add_nodes(vec![
+        Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::Buffer(
+            s_a.clone(),
+        )))),
+        Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::Block(
+            Rc::new(block),
+        )))),
+    ]);
+    let mut pass = AllocateVar::new();
+    pass.run(&mut graph);
+    let code = pass.code();
+    let mut lines = code.lines();
+    assert!(lines
+        .next()
+        .unwrap()
+        .starts_with("using SharedsA = SharedTile<cutlass::half_t, "));
+    assert_eq!(
+        lines.next(),
+        Some(
+            "SharedsA sA[3] = {SharedsA(sA_ptr + 0), SharedsA(sA_ptr + 2048), \
+             SharedsA(sA_ptr + 4096)};"
+        )
+    );
+}
+
+#[test]
+fn test_short_pipeline() {
+    let _guard = setup();
+
+    let k = fixed_ivar("k", 1);
+
+    let g_a = Rc::new(BufBuilder::row_major_global_tile("gA", &[256, 256]));
+    let s_a = Rc::new(BufBuilder::row_major_shared_tile("sA", &[64, 32]));
+
+    let mut map = AccessMap::new(1, vec![1]);
+    map.add_iter_vars(vec![k.clone()]);
+    map.add_access_matrixs(vec![
+        AccessMatrix(vec![vec![1]]),
+        AccessMatrix(vec![vec![0]]),
+    ]);
+    let g2s = Rc::new(AttachedEdge::new(g_a.clone(), s_a.clone(), Rc::new(map)));
+
+    let subgraph = Rc::new(RefCell::new(ThrillerGraph::new()));
+    let mut block = ThrillerBlock::new(vec![g2s], vec![], subgraph, vec![k]);
+    block.set_stages(3);
+
+    // The loop runs once, the second stage is an empty group so that the
+    // wait for at most 2 pending groups still covers stage 0.
+    let code = block.emit().unwrap();
+    let expected = format!(
+        "loader_tile_g2s_{ga}_to_{sa}(gA(1 * 0), sA[0](0 * 0));\n\
+         cute::cp_async_fence();\n\
+         cute::cp_async_fence();\n\
+         for(int k = 0; k < 1; ++k){{\n\
+         \x20   if(k + 2 < 1){{\n\
+         \x20       loader_tile_g2s_{ga}_to_{sa}(gA(1 * (k + 2)), sA[(k + 2) % 3](0 * (k + 2)));\n\
+         \x20   }}\n\
+         \x20   cute::cp_async_fence();\n\
+         \x20   cute::cp_async_wait<2>();\n\
+         \x20   __syncthreads();\n\
+         \x20   __syncthreads();\n\
+         }}\n\
+         cute::cp_async_wait<0>();\n\
+         __syncthreads();\n",
+        ga = g_a.get_id(),
+        sa = s_a.get_id()
+    );
+    assert_eq!(code, expected);
+}
+
+#[test]
+fn test_load_sync() {
+    let _guard = setup();
+
+    let k = fixed_ivar("k", 8);
+
+    let g_a = Rc::new(BufBuilder::row_major_global_tile("gA", &[256, 256]));
+    let s_a = Rc::new(BufBuilder::row_major_shared_tile("sA", &[64, 32]));
+    let r_a = Rc::new(BufBuilder::row_major_reg_tile("rA", &[64, 32]));
+    let g_b = Rc::new(BufBuilder::row_major_global_tile("gB", &[256, 256]));
+    let r_b = Rc::new(BufBuilder::row_major_reg_tile("rB", &[64, 32]));
+
+    let map = |coef: usize| {
+        let mut map = AccessMap::new(1, vec![1]);
+        map.add_iter_vars(vec![k.clone()]);
+        map.add_access_matrixs(vec![
+            AccessMatrix(vec![vec![coef]]),
+            AccessMatrix(vec![vec![0]]),
+        ]);
+        Rc::new(map)
+    };
+
+    // gA -> sA -> rA, gB -> rB
+    let inputs = vec![
+        Rc::new(AttachedEdge::new(g_a.clone(), s_a.clone(), map(1))),
+        Rc::new(AttachedEdge::new(s_a.clone(), r_a.clone(), map(0))),
+        Rc::new(AttachedEdge::new(g_b.clone(), r_b.clone(), map(1))),
+    ];
+
+    let subgraph = Rc::new(RefCell::new(ThrillerGraph::new()));
+    let block = ThrillerBlock::new(inputs, vec![], subgraph, vec![k]);
+
+    // The async copy into `sA` is waited for before `sA` is read, the
+    // register loads need no barrier. `sA` is only overwritten by the next
+    // iteration once every thread has read it.
+    let code = block.emit().unwrap();
+    let expected = format!(
+        "for(int k = 0; k < 8; ++k){{\n\
+         \x20   loader_tile_g2s_{ga}_to_{sa}(gA(1 * k), sA(0 * k));\n\
+         \x20   cute::cp_async_fence();\n\
+         \x20   cute::cp_async_wait<0>();\n\
+         \x20   __syncthreads();\n\
+         \x20   loader_tile_s2r_{sa}_to_{ra}(sA(0 * k), rA(0 * k));\n\
+         \x20   loader_tile_g2r_{gb}_to_{rb}(gB(1 * k), rB(0 * k));\n\
+         \x20   __syncthreads();\n\
+         }}\n\
+         __syncthreads();\n",
+        ga = g_a.get_id(),
+        sa = s_a.get_id(),
+        ra = r_a.get_id(),
+        gb = g_b.get_id(),
+        rb = r_b.get_id()
+    );
+    assert_eq!(code, expected);
+
+    // A warp staging its own tiles only synchronizes the warp.
+    let mut warp = block.clone();
+    warp.set_thread_scope(ThreadScope::Warp);
+    assert_eq!(
+        warp.emit().unwrap(),
+        expected.replace("__syncthreads();", "__syncwarp();")
+    );
+
+    // A group of warps synchronizes on its named barrier.
+    let mut warps = block.clone();
+    warps.set_thread_scope(ThreadScope::Warps {
+        id: 1,
+        threads: 128,
+    });
+    assert_eq!(
+        warps.emit().unwrap(),
+        expected.replace(
+            "__syncthreads();",
+            "asm volatile(\"bar.sync %0, %1;\" :: \"r\"(1), \"r\"(128));"
+        )
+    );
+}
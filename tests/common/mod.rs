@@ -0,0 +1,39 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::rc::Rc;
+use std::sync::{Mutex, MutexGuard, Once};
+
+use thriller_core::{
+    initialize, AccessMap, AccessMatrix, AccessOffset, IterationBound, IterationVar,
+};
+
+/// `initialize` can only run once per process and IDs are handed out by a
+/// global counter, so tests run one at a time.
+pub fn setup() -> MutexGuard<'static, ()> {
+    static INIT: Once = Once::new();
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    INIT.call_once(initialize);
+    LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn fixed_ivar(name: &str, upper: usize) -> Rc<IterationVar> {
+    Rc::new(IterationVar::new(
+        name,
+        (IterationBound::Fixed(0), IterationBound::Fixed(upper)),
+    ))
+}
+
+pub fn access(
+    ivars: &[Rc<IterationVar>],
+    matrixs: Vec<Vec<Vec<usize>>>,
+    offsets: Vec<Vec<usize>>,
+) -> Rc<AccessMap> {
+    let mut map = AccessMap::new(ivars.len(), vec![offsets[0].len()]);
+    map.add_iter_vars(ivars.to_vec());
+    map.add_access_matrixs(matrixs.into_iter().map(AccessMatrix).collect());
+    map.add_access_offsets(offsets.into_iter().map(AccessOffset).collect());
+    Rc::new(map)
+}
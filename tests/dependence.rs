@@ -0,0 +1,229 @@
+use std::{cell::RefCell, rc::Rc};
+
+use thriller_core::{
+    AccessKind, AttachedEdge, Buffer, DependenceAnalysis, DependenceKind, Distance, Gemm,
+    IterationBound, IterationVar, MemoryAccess, ThrillerBlock, ThrillerGraph, ThrillerNode,
+    ThrillerNodeInner,
+};
+
+use thriller_utils::BufBuilder;
+
+mod common;
+use common::{access, fixed_ivar, setup};
+
+fn single(
+    buf: &Rc<Buffer>,
+    ivar: &Rc<IterationVar>,
+    row: Vec<usize>,
+    offset: usize,
+    kind: AccessKind,
+) -> MemoryAccess {
+    MemoryAccess::new(
+        buf.clone(),
+        access(
+            std::slice::from_ref(ivar),
+            vec![vec![row]],
+            vec![vec![offset]],
+        ),
+        0,
+        kind,
+    )
+}
+
+#[test]
+fn test_dependence_analysis() {
+    let _guard = setup();
+
+    let m = Rc::new(IterationVar::new(
+        "m",
+        (IterationBound::Fixed(0), IterationBound::Fixed(4)),
+    ));
+    let k = Rc::new(IterationVar::new(
+        "k",
+        (IterationBound::Fixed(0), IterationBound::Fixed(8)),
+    ));
+    let ivars = vec![m.clone(), k.clone()];
+    let analysis = DependenceAnalysis::new(ivars.clone());
+
+    let s_x = Rc::new(BufBuilder::row_major_shared_tile("sX", &[64, 64]));
+    let s_y = Rc::new(BufBuilder::row_major_shared_tile("sY", &[64, 64]));
+
+    // sX(k + 1) = ...; ... = sX(k);
+    let inner = DependenceAnalysis::new(vec![k.clone()]);
+    let write = single(&s_x, &k, vec![1], 1, AccessKind::Write);
+    let read = single(&s_x, &k, vec![1], 0, AccessKind::Read);
+    let dependence = inner.analyze(&write, &read).unwrap();
+    assert_eq!(dependence.get_kind(), DependenceKind::ReadAfterWrite);
+    assert_eq!(dependence.get_distance(), &vec![Distance::Exact(1)]);
+    assert_eq!(dependence.get_carried_level(), Some(0));
+
+    // In the `(m, k)` nest every `m` iteration touches the same elements.
+    let dependence = analysis.analyze(&write, &read).unwrap();
+    assert_eq!(
+        dependence.get_distance(),
+        &vec![Distance::Any, Distance::Exact(1)]
+    );
+    // An `m` distance of -1 would make the dependence run the other way
+    // with distance (1, -1), so `k` may not move outwards.
+    assert!(dependence.is_preserved_by(&[0, 1]));
+    assert!(!dependence.is_preserved_by(&[1, 0]));
+
+    // sX(k) = ...; ... = sX(k + 1); reads the element before it is written.
+    let write = single(&s_x, &k, vec![1], 0, AccessKind::Write);
+    let read = single(&s_x, &k, vec![1], 1, AccessKind::Read);
+    let dependence = inner.analyze(&write, &read).unwrap();
+    assert_eq!(dependence.get_kind(), DependenceKind::WriteAfterRead);
+    assert_eq!(dependence.get_distance(), &vec![Distance::Exact(1)]);
+
+    // sX(m, k) = ...; ... = sX(m, k);
+    let write = MemoryAccess::new(
+        s_x.clone(),
+        access(&ivars, vec![vec![vec![1, 0], vec![0, 1]]], vec![vec![0, 0]]),
+        0,
+        AccessKind::Write,
+    );
+    let read = MemoryAccess::new(
+        s_x.clone(),
+        access(&ivars, vec![vec![vec![1, 0], vec![0, 1]]], vec![vec![0, 0]]),
+        0,
+        AccessKind::Read,
+    );
+    let dependence = analysis.analyze(&write, &read).unwrap();
+    assert_eq!(
+        dependence.get_distance(),
+        &vec![Distance::Exact(0), Distance::Exact(0)]
+    );
+    assert_eq!(dependence.get_carried_level(), None);
+
+    // Out of the iteration domain of `k`.
+    let write = single(&s_x, &k, vec![1], 8, AccessKind::Write);
+    let read = single(&s_x, &k, vec![1], 0, AccessKind::Read);
+    assert!(analysis.analyze(&write, &read).is_none());
+
+    // Odd and even elements never overlap.
+    let write = single(&s_x, &k, vec![2], 1, AccessKind::Write);
+    let read = single(&s_x, &k, vec![2], 0, AccessKind::Read);
+    assert!(analysis.analyze(&write, &read).is_none());
+
+    // Different buffers and read-read pairs are independent.
+    let read_x = single(&s_x, &k, vec![1], 0, AccessKind::Read);
+    let read_y = single(&s_y, &k, vec![1], 0, AccessKind::Read);
+    let write_y = single(&s_y, &k, vec![1], 0, AccessKind::Write);
+    assert!(analysis.analyze(&read_x, &read_x).is_none());
+    assert!(analysis.analyze(&read_x, &write_y).is_none());
+    assert!(analysis.analyze(&write_y, &read_y).is_some());
+
+    // gA(m, k) -> sA(k) followed by sA(k) -> rA in the same loop nest.
+    let g_a = Rc::new(BufBuilder::row_major_global_tile("gA", &[256, 256]));
+    let s_a = Rc::new(BufBuilder::row_major_shared_tile("sA", &[64, 64]));
+    let r_a = Rc::new(BufBuilder::row_major_reg_tile("rA", &[64, 64]));
+    let load_g2s = Rc::new(AttachedEdge::new(
+        g_a,
+        s_a.clone(),
+        access(
+            &ivars,
+            vec![vec![vec![1, 0], vec![0, 1]], vec![vec![0, 1]]],
+            vec![vec![0, 0], vec![0]],
+        ),
+    ));
+    let load_s2r = Rc::new(AttachedEdge::new(
+        s_a,
+        r_a,
+        access(
+            &[k.clone(), m.clone()],
+            vec![vec![vec![1, 0]], vec![vec![0, 0]]],
+            vec![vec![0], vec![0]],
+        ),
+    ));
+
+    let subgraph = Rc::new(RefCell::new(ThrillerGraph::new()));
+    let block = ThrillerBlock::new(vec![load_g2s, load_s2r], vec![], subgraph, ivars);
+    let analysis = DependenceAnalysis::from_block(&block);
+
+    // sA(k) is overwritten by every `m` iteration before being read again,
+    // rA is never read in the loop.
+    let dependences = analysis.get_dependences();
+    let kinds = dependences
+        .iter()
+        .map(|dependence| dependence.get_kind())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![
+            DependenceKind::WriteAfterWrite,
+            DependenceKind::ReadAfterWrite
+        ]
+    );
+    for dependence in dependences {
+        assert_eq!(
+            dependence.get_distance(),
+            &vec![Distance::Any, Distance::Exact(0)]
+        );
+    }
+}
+
+#[test]
+fn test_op_dependences() {
+    let _guard = setup();
+
+    let m = fixed_ivar("m", 4);
+    let n = fixed_ivar("n", 4);
+    let k = fixed_ivar("k", 8);
+    let ivars = vec![m.clone(), n.clone(), k.clone()];
+
+    let node = |name: &str| {
+        let buf = Rc::new(BufBuilder::row_major_reg_tile(name, &[4, 8, 16, 16]));
+        Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::Buffer(
+            buf,
+        ))))
+    };
+    let gemm = |c_access: Vec<Vec<usize>>| {
+        // rA[m][k], rB[k][n], rC[...]
+        let access_map = access(
+            &ivars,
+            vec![
+                vec![vec![1, 0, 0], vec![0, 0, 1]],
+                vec![vec![0, 0, 1], vec![0, 1, 0]],
+                c_access,
+            ],
+            vec![vec![0, 0], vec![0, 0], vec![0, 0]],
+        );
+        let gemm = Gemm::new(vec![node("rA"), node("rB")], node("rC"), access_map);
+        let mut subgraph = ThrillerGraph::new();
+        subgraph.add_nodes(vec![Rc::new(RefCell::new(ThrillerNode::new(
+            ThrillerNodeInner::op(gemm),
+        )))]);
+        subgraph.connect();
+        ThrillerBlock::new(
+            vec![],
+            vec![],
+            Rc::new(RefCell::new(subgraph)),
+            ivars.clone(),
+        )
+    };
+
+    // rC[m][n] is accumulated over every `k` iteration: the update depends
+    // on the previous one through the read and the write of rC.
+    let analysis = DependenceAnalysis::from_block(&gemm(vec![vec![1, 0, 0], vec![0, 1, 0]]));
+    let dependences = analysis.get_dependences();
+    assert_eq!(dependences.len(), 2);
+    assert_eq!(dependences[0].get_kind(), DependenceKind::WriteAfterRead);
+    assert_eq!(dependences[1].get_kind(), DependenceKind::WriteAfterWrite);
+    for dependence in dependences {
+        assert_eq!(
+            dependence.get_distance(),
+            &vec![Distance::Exact(0), Distance::Exact(0), Distance::Any]
+        );
+        assert!(dependence.is_preserved_by(&[2, 0, 1]));
+    }
+
+    // rC[m][0] is accumulated over every `n` and `k` iteration, in order.
+    let analysis = DependenceAnalysis::from_block(&gemm(vec![vec![1, 0, 0], vec![0, 0, 0]]));
+    let dependence = &analysis.get_dependences()[0];
+    assert_eq!(
+        dependence.get_distance(),
+        &vec![Distance::Exact(0), Distance::Any, Distance::Any]
+    );
+    assert!(dependence.is_preserved_by(&[1, 0, 2]));
+    assert!(!dependence.is_preserved_by(&[0, 2, 1]));
+}
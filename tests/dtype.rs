@@ -0,0 +1,65 @@
+use std::str::FromStr;
+
+use thriller_core::DataType;
+
+const DTYPES: [DataType; 10] = [
+    DataType::Float32,
+    DataType::Float64,
+    DataType::Half,
+    DataType::Cutlasshalf,
+    DataType::BF16,
+    DataType::TF32,
+    DataType::Float8E4M3,
+    DataType::Float8E5M2,
+    DataType::Int8,
+    DataType::Int32,
+];
+
+#[test]
+fn test_dtype_round_trip() {
+    for dtype in DTYPES {
+        assert_eq!(DataType::from_str(&dtype.to_string()).unwrap(), dtype);
+    }
+
+    assert!(DataType::from_str("float16").is_err());
+}
+
+#[test]
+fn test_dtype_names() {
+    // C++ types for declarations.
+    assert_eq!(DataType::BF16.to_string(), "cutlass::bfloat16_t");
+    assert_eq!(DataType::Cutlasshalf.to_string(), "cutlass::half_t");
+
+    // Identifiers for specialized helpers.
+    let names = DTYPES.map(|dtype| dtype.short_name());
+    assert_eq!(
+        names,
+        ["f32", "f64", "f16", "f16", "bf16", "tf32", "f8e4m3", "f8e5m2", "i8", "i32"]
+    );
+    assert!(names
+        .iter()
+        .all(|name| name.chars().all(|c| c.is_ascii_alphanumeric())));
+}
+
+#[test]
+fn test_dtype_sizes() {
+    let sizes = DTYPES.map(|dtype| dtype.size_in_bytes());
+    assert_eq!(sizes, [4, 8, 2, 2, 2, 4, 1, 1, 1, 4]);
+}
+
+#[test]
+fn test_accumulator() {
+    assert_eq!(DataType::Int8.accumulator(), DataType::Int32);
+    assert_eq!(DataType::Float8E4M3.accumulator(), DataType::Float32);
+    assert_eq!(DataType::Cutlasshalf.accumulator(), DataType::Float32);
+    assert_eq!(DataType::Float64.accumulator(), DataType::Float64);
+
+    // Quantised products need a wider accumulator.
+    assert!(DataType::Int8.can_accumulate_in(DataType::Int32));
+    assert!(!DataType::Int8.can_accumulate_in(DataType::Int8));
+    assert!(!DataType::Float8E5M2.can_accumulate_in(DataType::Float8E5M2));
+
+    assert!(DataType::Half.can_accumulate_in(DataType::Half));
+    assert!(DataType::Half.can_accumulate_in(DataType::Float32));
+    assert!(!DataType::Float32.can_accumulate_in(DataType::Half));
+}
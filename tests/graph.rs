@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::{cell::RefCell, rc::Rc};
+
+use thriller_core::{
+    BufType, Buffer, Convert, DataType, Layout, Task, ThrillerEdge, ThrillerError, ThrillerGraph,
+    ThrillerNode, ThrillerNodeInner,
+};
+
+use thriller_utils::BufBuilder;
+
+mod common;
+use common::setup;
+
+fn buffer_node(name: &str) -> Rc<RefCell<ThrillerNode>> {
+    let buf = Rc::new(BufBuilder::row_major_reg_tile(name, &[16, 16]));
+    Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::Buffer(
+        buf,
+    ))))
+}
+
+#[test]
+fn test_topo_sort_cycle() {
+    let _guard = setup();
+
+    let a = buffer_node("a");
+    let b = buffer_node("b");
+    let c = buffer_node("c");
+
+    let mut graph = ThrillerGraph::new();
+    graph.add_nodes(vec![a.clone(), b.clone(), c.clone()]);
+    graph.add_edges(vec![
+        Rc::new(ThrillerEdge::new(a.clone(), b.clone())),
+        Rc::new(ThrillerEdge::new(b.clone(), c.clone())),
+        Rc::new(ThrillerEdge::new(c.clone(), b.clone())),
+    ]);
+    graph.connect();
+
+    match graph.topo_sort() {
+        Err(ThrillerError::GraphCycle(cycle)) => {
+            assert_eq!(cycle.len(), 2);
+            assert!(cycle.contains(&"b".to_string()));
+            assert!(cycle.contains(&"c".to_string()));
+        }
+        _ => panic!("expected a cycle error"),
+    }
+}
+
+#[test]
+fn test_topo_sort_deterministic() {
+    let _guard = setup();
+
+    let cast = |src: &str, dst: &str| {
+        let tile = |name: &str, dtype: DataType| {
+            Rc::new(Buffer::new(
+                name,
+                BufType::RegTile,
+                &[16, 16],
+                Layout::RowMajor,
+                dtype,
+            ))
+        };
+        let cast = Convert::new(tile(src, DataType::Float32), tile(dst, DataType::Half));
+        Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::Op(
+            Box::new(cast),
+        ))))
+    };
+
+    let nodes = vec![
+        cast("a0", "a1"),
+        cast("b0", "b1"),
+        cast("c0", "c1"),
+        cast("d0", "d1"),
+    ];
+    // Reverse the insertion order through the priority.
+    let priorities: HashMap<usize, isize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| (node.borrow().get_id(), -(index as isize)))
+        .collect();
+
+    let mut graph = ThrillerGraph::new();
+    graph.add_nodes(nodes);
+    graph.connect();
+
+    let expected = "cast_f32_to_f16(a0, a1);\n\
+                    cast_f32_to_f16(b0, b1);\n\
+                    cast_f32_to_f16(c0, c1);\n\
+                    cast_f32_to_f16(d0, d1);\n";
+    for _ in 0..8 {
+        assert_eq!(graph.emit().unwrap(), expected);
+    }
+
+    graph.set_priority(move |node| priorities[&node.get_id()]);
+    let code = graph.emit().unwrap();
+    assert!(code.starts_with("cast_f32_to_f16(d0, d1);"));
+    assert!(code.ends_with("cast_f32_to_f16(a0, a1);\n"));
+}
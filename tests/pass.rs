@@ -0,0 +1,862 @@
+use std::{cell::RefCell, rc::Rc};
+
+use thriller_core::{
+    AccessMap, AllocateVar, AttachedEdge, BarrierElimination, BatchedGemm, BufType, Buffer,
+    Convert, DataType, Gemm, GraphPass, IterationBound, IterationVar, Layout, LoopFusion,
+    LoopInterchange, LoopTiling, Map, RegisterPressure, Shape, ShapeValidation,
+    SharedMemoryPlanner, Swizzle, Task, ThrillerBlock, ThrillerEdge, ThrillerEngine, ThrillerError,
+    ThrillerGraph, ThrillerNode, ThrillerNodeInner, UnaryOp,
+};
+
+use thriller_utils::BufBuilder;
+
+mod common;
+use common::{access, fixed_ivar, setup};
+
+fn buffer_node(buf: Buffer) -> Rc<RefCell<ThrillerNode>> {
+    Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::Buffer(
+        Rc::new(buf),
+    ))))
+}
+
+fn block_graph(block: ThrillerBlock) -> ThrillerGraph {
+    let mut graph = ThrillerGraph::new();
+    graph.add_nodes(vec![Rc::new(RefCell::new(ThrillerNode::new(
+        ThrillerNodeInner::Block(Rc::new(block)),
+    )))]);
+    graph.connect();
+    graph
+}
+
+#[test]
+fn test_allocate_var_layouts() {
+    let _guard = setup();
+
+    let padded = Shape::with_strides(&[64, 32], &[40, 1]).unwrap();
+
+    let mut graph = ThrillerGraph::new();
+    graph.add_nodes(vec![
+        buffer_node(Buffer::new(
+            "gA",
+            BufType::GlobalTile,
+            &[256, 256],
+            Layout::RowMajor,
+            DataType::Float32,
+        )),
+        buffer_node(Buffer::with_shape(
+            "sA",
+            BufType::SharedTile,
+            padded,
+            DataType::Cutlasshalf,
+        )),
+        buffer_node(BufBuilder::swizzled_shared_tile(
+            "sB",
+            &[64, 64],
+            Swizzle::new(3, 3, 3),
+        )),
+    ]);
+
+    let mut pass = AllocateVar::new();
+    pass.run(&mut graph);
+
+    assert_eq!(
+        pass.code(),
+        "using GlobalgA = GlobalTile<float, cute::Layout<cute::Shape<cute::Int<256>, cute::Int<256>>, \
+         cute::Stride<cute::Int<256>, cute::Int<1>>>>;\n\
+         GlobalgA gA;\n\
+         using SharedsA = SharedTile<cutlass::half_t, cute::Layout<cute::Shape<cute::Int<64>, cute::Int<32>>, \
+         cute::Stride<cute::Int<40>, cute::Int<1>>>>;\n\
+         SharedsA sA(sA_ptr);\n\
+         using SharedsB = SharedTile<cutlass::half_t, cute::ComposedLayout<cute::Swizzle<3, 3, 3>, cute::_0, \
+         cute::Layout<cute::Shape<cute::Int<64>, cute::Int<64>>, cute::Stride<cute::Int<64>, cute::Int<1>>>>>;\n\
+         SharedsB sB(sB_ptr);\n"
+    );
+}
+
+#[test]
+fn test_loop_interchange() {
+    let _guard = setup();
+
+    let m = fixed_ivar("m", 4);
+    let k = fixed_ivar("k", 8);
+
+    let g_x = Rc::new(BufBuilder::row_major_global_tile("gX", &[256, 256]));
+    let s_x = Rc::new(BufBuilder::row_major_shared_tile("sX", &[64, 64]));
+    let r_x = Rc::new(BufBuilder::row_major_reg_tile("rX", &[16, 16]));
+
+    // gX(m, k) -> sX(m, k + offset) -> rX
+    let build = |offset: usize| {
+        let g2s = Rc::new(AttachedEdge::new(
+            g_x.clone(),
+            s_x.clone(),
+            access(
+                &[m.clone(), k.clone()],
+                vec![vec![vec![1, 0], vec![0, 1]], vec![vec![1, 0], vec![0, 1]]],
+                vec![vec![0, 0], vec![0, offset]],
+            ),
+        ));
+        let s2r = Rc::new(AttachedEdge::new(
+            s_x.clone(),
+            r_x.clone(),
+            access(
+                &[m.clone(), k.clone()],
+                vec![vec![vec![1, 0], vec![0, 1]], vec![vec![0, 0], vec![0, 0]]],
+                vec![vec![1, 0], vec![0, 0]],
+            ),
+        ));
+        let subgraph = Rc::new(RefCell::new(ThrillerGraph::new()));
+        ThrillerBlock::new(vec![g2s, s2r], vec![], subgraph, vec![m.clone(), k.clone()])
+    };
+
+    let emit = |graph: &ThrillerGraph| graph.emit().unwrap();
+
+    // sX(m + 1, k) is read one `m` iteration before it is written, the
+    // distance (1, 0) of this anti dependence stays positive as (0, 1).
+    let mut graph = block_graph(build(0));
+    let mut pass = LoopInterchange::new(&["k", "m"]);
+    pass.run(&mut graph);
+    assert_eq!(pass.interchanged().len(), 1);
+    assert!(pass.rejected().is_empty());
+
+    let code = emit(&graph);
+    assert!(code.starts_with("for(int k = 0; k < 8; ++k){\n    for(int m = 0; m < 4; ++m){\n"));
+    // The access matrix columns follow the new loop order.
+    assert!(code.contains(&format!(
+        "loader_tile_g2s_{}_to_{}(gX(0 * k + 1 * m, 1 * k + 0 * m), sX(0 * k + 1 * m, 1 * k + 0 * m));",
+        g_x.get_id(),
+        s_x.get_id()
+    )));
+
+    // Writing sX(m, k + 1) gives an anti dependence with distance
+    // (1, -1), which would become (-1, 1).
+    let mut graph = block_graph(build(1));
+    let before = emit(&graph);
+    let mut pass = LoopInterchange::new(&["k", "m"]);
+    pass.run(&mut graph);
+    assert!(pass.interchanged().is_empty());
+    assert_eq!(pass.rejected().len(), 1);
+    assert_eq!(emit(&graph), before);
+}
+
+#[test]
+fn test_loop_fusion() {
+    let _guard = setup();
+
+    let g_a = Rc::new(BufBuilder::row_major_global_tile("gA", &[256, 256]));
+    let g_x = Rc::new(BufBuilder::row_major_global_tile("gX", &[256, 256]));
+    let g_y = Rc::new(BufBuilder::row_major_global_tile("gY", &[256, 256]));
+    let r_a = Rc::new(BufBuilder::row_major_reg_tile("rA", &[16, 16]));
+    let r_x = Rc::new(BufBuilder::row_major_reg_tile("rX", &[16, 16]));
+
+    let identity = |ivar: &Rc<IterationVar>| {
+        access(
+            std::slice::from_ref(ivar),
+            vec![vec![vec![1]], vec![vec![1]]],
+            vec![vec![0], vec![0]],
+        )
+    };
+    let edge = |src: &Rc<Buffer>, dst: &Rc<Buffer>, ivar: &Rc<IterationVar>| {
+        Rc::new(AttachedEdge::new(src.clone(), dst.clone(), identity(ivar)))
+    };
+    let block = |input: Rc<AttachedEdge>,
+                 output: Rc<AttachedEdge>,
+                 ivar: &Rc<IterationVar>,
+                 body: Rc<Buffer>| {
+        let relu = Map::unary(UnaryOp::Relu, body.clone(), body).unwrap();
+        let mut subgraph = ThrillerGraph::new();
+        subgraph.add_nodes(vec![Rc::new(RefCell::new(ThrillerNode::new(
+            ThrillerNodeInner::op(relu),
+        )))]);
+        Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::Block(
+            Rc::new(ThrillerBlock::new(
+                vec![input],
+                vec![output],
+                Rc::new(RefCell::new(subgraph)),
+                vec![ivar.clone()],
+            )),
+        ))))
+    };
+
+    // gA -> [m: gA -> rA, relu(rA), rA -> gX] -> gX
+    //    -> [n: gX -> rX, relu(rX), rX -> gY] -> gY
+    let m = fixed_ivar("m", 4);
+    let n = fixed_ivar("n", 4);
+    let producer = block(edge(&g_a, &r_a, &m), edge(&r_a, &g_x, &m), &m, r_a.clone());
+    let consumer = block(edge(&g_x, &r_x, &n), edge(&r_x, &g_y, &n), &n, r_x.clone());
+
+    let nodes = vec![
+        buffer_node((*g_a).clone()),
+        producer.clone(),
+        buffer_node((*g_x).clone()),
+        consumer.clone(),
+        buffer_node((*g_y).clone()),
+    ];
+    let mut graph = ThrillerGraph::new();
+    graph.add_nodes(nodes.clone());
+    graph.add_edges(
+        nodes
+            .windows(2)
+            .map(|pair| Rc::new(ThrillerEdge::new(pair[0].clone(), pair[1].clone())))
+            .collect(),
+    );
+    graph.connect();
+
+    let mut pass = LoopFusion::new();
+    pass.run(&mut graph);
+    assert_eq!(pass.fused().len(), 1);
+
+    // The intermediate gX and the consumer are gone.
+    let sorted = graph.topo_sort().unwrap();
+    assert_eq!(sorted.len(), 3);
+    assert!(sorted.iter().all(|node| !Rc::ptr_eq(node, &consumer)));
+
+    let code = graph.emit().unwrap();
+    assert_eq!(code.matches("for(int").count(), 1);
+    // The forwarded tile is copied between the two bodies.
+    let position = |pattern: &str| code.find(pattern).unwrap();
+    assert!(position("compute::relu(rA, rA);") < position("copy_tile_r2r(rA, rX);"));
+    assert!(position("copy_tile_r2r(rA, rX);") < position("compute::relu(rX, rX);"));
+    assert!(code.contains("gY(1 * m)"));
+    assert!(!code.contains("gX("));
+
+    // Blocks ordered by a direct edge are fused as well.
+    let producer = block(edge(&g_a, &r_a, &m), edge(&r_a, &g_x, &m), &m, r_a.clone());
+    let consumer = block(edge(&g_x, &r_x, &n), edge(&r_x, &g_y, &n), &n, r_x.clone());
+    let nodes = vec![
+        buffer_node((*g_a).clone()),
+        producer.clone(),
+        consumer.clone(),
+        buffer_node((*g_y).clone()),
+    ];
+    let mut graph = ThrillerGraph::new();
+    graph.add_nodes(nodes.clone());
+    graph.add_edges(
+        nodes
+            .windows(2)
+            .map(|pair| Rc::new(ThrillerEdge::new(pair[0].clone(), pair[1].clone())))
+            .collect(),
+    );
+    graph.connect();
+
+    let mut pass = LoopFusion::new();
+    pass.run(&mut graph);
+    assert_eq!(pass.fused().len(), 1);
+    assert_eq!(graph.topo_sort().unwrap().len(), 3);
+    assert_eq!(graph.emit().unwrap().matches("for(int").count(), 1);
+}
+
+#[test]
+fn test_loop_tiling() {
+    let _guard = setup();
+
+    let m = Rc::new(IterationVar::new(
+        "m",
+        (IterationBound::Fixed(16), IterationBound::Fixed(80)),
+    ));
+    let g_x = Rc::new(BufBuilder::row_major_global_tile("gX", &[256, 256]));
+    let r_x = Rc::new(BufBuilder::row_major_reg_tile("rX", &[16, 16]));
+
+    // gX(2 * m, 1) -> rX
+    let build = || {
+        let g2r = Rc::new(AttachedEdge::new(
+            g_x.clone(),
+            r_x.clone(),
+            access(
+                std::slice::from_ref(&m),
+                vec![vec![vec![2], vec![0]], vec![vec![0], vec![0]]],
+                vec![vec![0, 1], vec![0, 0]],
+            ),
+        ));
+        let subgraph = Rc::new(RefCell::new(ThrillerGraph::new()));
+        ThrillerBlock::new(vec![g2r], vec![], subgraph, vec![m.clone()])
+    };
+
+    let mut graph = block_graph(build());
+    let mut pass = LoopTiling::new("m", 16);
+    pass.run(&mut graph);
+    assert_eq!(pass.tiled().len(), 1);
+
+    let code = graph.emit().unwrap();
+    assert!(code.starts_with(
+        "for(int m_o = 0; m_o < 4; ++m_o){\n    for(int m_i = 0; m_i < 16; ++m_i){\n"
+    ));
+    assert!(code.contains(&format!(
+        "loader_tile_g2r_{}_to_{}(gX(32 * m_o + 2 * m_i + 32, 0 * m_o + 0 * m_i + 1), \
+         rX(0 * m_o + 0 * m_i, 0 * m_o + 0 * m_i));",
+        g_x.get_id(),
+        r_x.get_id()
+    )));
+
+    // The inner loop can be tiled again.
+    let mut pass = LoopTiling::new("m_i", 4);
+    pass.run(&mut graph);
+    assert_eq!(pass.tiled().len(), 1);
+    assert!(graph
+        .emit()
+        .unwrap()
+        .contains("gX(32 * m_o + 8 * m_i_o + 2 * m_i_i + 32"));
+
+    // 64 iterations cannot be tiled by 3.
+    let mut graph = block_graph(build());
+    let mut pass = LoopTiling::new("m", 3);
+    pass.run(&mut graph);
+    assert!(pass.tiled().is_empty());
+    assert_eq!(pass.rejected().len(), 1);
+
+    // The batch index of a batched GEMM in the body follows the tiling.
+    let b = fixed_ivar("b", 4);
+    let r_a = Rc::new(BufBuilder::row_major_reg_tile("rA", &[4, 16, 32]));
+    let r_b = Rc::new(BufBuilder::row_major_reg_tile("rB", &[4, 32, 8]));
+    let r_c = Rc::new(BufBuilder::row_major_reg_tile("rC", &[4, 16, 8]));
+    let gemm = BatchedGemm::new(r_a, r_b, r_c, b.clone()).unwrap();
+    let mut body = ThrillerGraph::new();
+    body.add_nodes(vec![Rc::new(RefCell::new(ThrillerNode::new(
+        ThrillerNodeInner::op(gemm),
+    )))]);
+    let block = ThrillerBlock::new(vec![], vec![], Rc::new(RefCell::new(body)), vec![b]);
+
+    let mut graph = block_graph(block);
+    let mut pass = LoopTiling::new("b", 2);
+    pass.run(&mut graph);
+    assert_eq!(pass.tiled().len(), 1);
+    assert!(graph
+        .emit()
+        .unwrap()
+        .contains("compute::gemm_(rA[2 * b_o + 1 * b_i + 0], rB[2 * b_o + 1 * b_i + 0], rC[2 * b_o + 1 * b_i + 0]);"));
+}
+
+#[test]
+fn test_barrier_elimination() {
+    let _guard = setup();
+
+    let n = fixed_ivar("n", 4);
+    let k = fixed_ivar("k", 8);
+
+    let g_a = Rc::new(BufBuilder::row_major_global_tile("gA", &[256, 256]));
+    let s_a = Rc::new(BufBuilder::row_major_shared_tile("sA", &[64, 64]));
+    let r_a = Rc::new(BufBuilder::row_major_reg_tile("rA", &[16, 16]));
+    let g_b = Rc::new(BufBuilder::row_major_global_tile("gB", &[256, 256]));
+    let r_b = Rc::new(BufBuilder::row_major_reg_tile("rB", &[16, 16]));
+    let g_c = Rc::new(BufBuilder::row_major_global_tile("gC", &[256, 256]));
+
+    let edge = |src: &Rc<Buffer>, dst: &Rc<Buffer>, ivar: &Rc<IterationVar>| {
+        Rc::new(AttachedEdge::new(
+            src.clone(),
+            dst.clone(),
+            access(
+                std::slice::from_ref(ivar),
+                vec![vec![vec![1]], vec![vec![1]]],
+                vec![vec![0], vec![0]],
+            ),
+        ))
+    };
+    let block_node = |block: ThrillerBlock| {
+        Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::Block(
+            Rc::new(block),
+        ))))
+    };
+
+    // for n: for k: gA -> sA -> rA, then rA -> gB after the `n` loop.
+    let inner = ThrillerBlock::new(
+        vec![edge(&g_a, &s_a, &k), edge(&s_a, &r_a, &k)],
+        vec![],
+        Rc::new(RefCell::new(ThrillerGraph::new())),
+        vec![k.clone()],
+    );
+    let mut body = ThrillerGraph::new();
+    body.add_nodes(vec![block_node(inner)]);
+    let store = Rc::new(AttachedEdge::new(
+        r_a.clone(),
+        g_b.clone(),
+        access(
+            std::slice::from_ref(&n),
+            vec![vec![vec![0]], vec![vec![0]]],
+            vec![vec![0], vec![0]],
+        ),
+    ));
+    let outer = ThrillerBlock::new(
+        vec![],
+        vec![store],
+        Rc::new(RefCell::new(body)),
+        vec![n.clone()],
+    );
+
+    // for n: gB -> rB, rB -> gC
+    let consumer = ThrillerBlock::new(
+        vec![edge(&g_b, &r_b, &n)],
+        vec![edge(&r_b, &g_c, &n)],
+        Rc::new(RefCell::new(ThrillerGraph::new())),
+        vec![n.clone()],
+    );
+
+    let nodes = vec![block_node(outer), block_node(consumer)];
+    let mut graph = ThrillerGraph::new();
+    graph.add_nodes(nodes.clone());
+    graph.add_edges(vec![Rc::new(ThrillerEdge::new(
+        nodes[0].clone(),
+        nodes[1].clone(),
+    ))]);
+    graph.connect();
+
+    let before = graph.emit().unwrap();
+    assert_eq!(before.matches("__syncthreads();").count(), 5);
+
+    let mut pass = BarrierElimination::new();
+    pass.run(&mut graph);
+
+    // Kept: the barrier after the g2s copy, the one closing the `k` body,
+    // as `sA` is overwritten in the next iteration, and the one after the
+    // `n` loop. Removed: the one after the `k` loop, which directly follows
+    // the barrier closing its body, and the last one, as `gC` is not
+    // accessed afterwards.
+    assert_eq!(pass.removed(), 2);
+    assert_eq!(pass.check().unwrap(), 2);
+    let code = graph.emit().unwrap();
+    assert_eq!(code.matches("__syncthreads();").count(), 3);
+
+    // A cyclic graph is reported instead of being left untouched.
+    let empty_block = || {
+        block_node(ThrillerBlock::new(
+            vec![],
+            vec![edge(&r_a, &g_b, &n)],
+            Rc::new(RefCell::new(ThrillerGraph::new())),
+            vec![n.clone()],
+        ))
+    };
+    let nodes = vec![empty_block(), empty_block()];
+    let mut graph = ThrillerGraph::new();
+    graph.add_nodes(nodes.clone());
+    graph.add_edges(vec![
+        Rc::new(ThrillerEdge::new(nodes[0].clone(), nodes[1].clone())),
+        Rc::new(ThrillerEdge::new(nodes[1].clone(), nodes[0].clone())),
+    ]);
+    graph.connect();
+
+    let mut pass = BarrierElimination::new();
+    pass.run(&mut graph);
+    assert!(matches!(pass.check(), Err(ThrillerError::GraphCycle(_))));
+}
+
+#[test]
+fn test_shape_validation() {
+    let _guard = setup();
+
+    let k = fixed_ivar("k", 4);
+
+    let g_a = Rc::new(BufBuilder::row_major_global_tile("gA", &[64, 256]));
+    let s_a = Rc::new(BufBuilder::row_major_shared_tile("sA", &[64, 48]));
+    let r_a = BufBuilder::row_major_reg_tile("rA", &[16, 32]);
+    let r_b = BufBuilder::row_major_reg_tile("rB", &[16, 32]);
+    let r_c = Rc::new(BufBuilder::row_major_reg_tile("rC", &[16, 32]));
+    let r_h = Rc::new(BufBuilder::row_major_reg_tile("rH", &[16, 16]));
+
+    // rA: 16x32 * rB: 16x32, the inner dimensions only agree with B transposed.
+    let gemm = Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::op(
+        Gemm::new(
+            vec![buffer_node(r_a), buffer_node(r_b)],
+            buffer_node((*r_h).clone()),
+            Rc::new(AccessMap::new(0, vec![])),
+        ),
+    ))));
+    let cast = Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::op(
+        Convert::new(r_c.clone(), r_h.clone()),
+    ))));
+    let mut body = ThrillerGraph::new();
+    body.add_nodes(vec![gemm, cast]);
+
+    // sA: 64x48 does not tile gA: 64x256.
+    let block = ThrillerBlock::new(
+        vec![Rc::new(AttachedEdge::new(
+            g_a,
+            s_a,
+            access(
+                std::slice::from_ref(&k),
+                vec![vec![vec![0], vec![1]], vec![vec![0], vec![0]]],
+                vec![vec![0, 0], vec![0, 0]],
+            ),
+        ))],
+        vec![],
+        Rc::new(RefCell::new(body)),
+        vec![k],
+    );
+    let mut graph = block_graph(block);
+
+    let mut pass = ShapeValidation::new();
+    pass.run(&mut graph);
+
+    let diagnostics = pass.diagnostics();
+    assert_eq!(diagnostics.len(), 3);
+    assert!(diagnostics
+        .iter()
+        .all(|d| matches!(d.get_error(), ThrillerError::ShapeMismatch)));
+    assert_eq!(diagnostics[0].get_location(), "gA -> sA");
+    assert!(diagnostics[1].get_location().starts_with("Gemm_"));
+    assert!(diagnostics[2].get_location().starts_with("Convert_"));
+
+    let mut gemm = Gemm::new(
+        vec![
+            buffer_node(BufBuilder::row_major_reg_tile("rA", &[16, 32])),
+            buffer_node(BufBuilder::row_major_reg_tile("rB", &[16, 32])),
+        ],
+        buffer_node((*r_h).clone()),
+        Rc::new(AccessMap::new(0, vec![])),
+    );
+    gemm.set_transpose(false, true);
+    assert!(gemm.validate().is_empty());
+
+    // gF: float is loaded into sH: half without a conversion.
+    let n = fixed_ivar("n", 1);
+    let g_f = Rc::new(Buffer::new(
+        "gF",
+        BufType::GlobalTile,
+        &[64, 64],
+        Layout::RowMajor,
+        DataType::Float32,
+    ));
+    let s_h = Rc::new(BufBuilder::row_major_shared_tile("sH", &[64, 64]));
+    let block = ThrillerBlock::new(
+        vec![Rc::new(AttachedEdge::new(
+            g_f.clone(),
+            s_h.clone(),
+            access(
+                std::slice::from_ref(&n),
+                vec![vec![vec![0], vec![0]], vec![vec![0], vec![0]]],
+                vec![vec![0, 0], vec![0, 0]],
+            ),
+        ))],
+        vec![],
+        Rc::new(RefCell::new(ThrillerGraph::new())),
+        vec![n],
+    );
+    let mut pass = ShapeValidation::new();
+    pass.run(&mut block_graph(block));
+    let diagnostics = pass.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert!(matches!(
+        diagnostics[0].get_error(),
+        ThrillerError::DataTypeMismatch
+    ));
+    assert_eq!(diagnostics[0].get_location(), "gF -> sH");
+
+    // Buffer nodes connected by a graph edge must hold the same data.
+    let nodes = vec![
+        buffer_node((*g_f).clone()),
+        buffer_node(Buffer::new(
+            "gG",
+            BufType::GlobalTile,
+            &[64, 32],
+            Layout::RowMajor,
+            DataType::Float32,
+        )),
+        buffer_node((*s_h).clone()),
+    ];
+    let mut graph = ThrillerGraph::new();
+    graph.add_nodes(nodes.clone());
+    graph.add_edges(vec![
+        Rc::new(ThrillerEdge::new(nodes[0].clone(), nodes[1].clone())),
+        Rc::new(ThrillerEdge::new(nodes[0].clone(), nodes[2].clone())),
+    ]);
+    graph.connect();
+
+    let mut pass = ShapeValidation::new();
+    pass.run(&mut graph);
+    let errors = pass
+        .diagnostics()
+        .iter()
+        .map(|d| (d.get_location().as_str(), d.get_error().clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(
+        errors[0],
+        ("gF -> gG", ThrillerError::ShapeMismatch)
+    ));
+    assert!(matches!(
+        errors[1],
+        ("gF -> sH", ThrillerError::DataTypeMismatch)
+    ));
+}
+
+#[test]
+fn test_shared_memory_planner() {
+    let _guard = setup();
+
+    let k = fixed_ivar("k", 8);
+
+    let shared = |name: &str, dims: &[usize], dtype: DataType| {
+        Rc::new(Buffer::new(
+            name,
+            BufType::SharedTile,
+            dims,
+            Layout::RowMajor,
+            dtype,
+        ))
+    };
+    let g_a = Rc::new(BufBuilder::row_major_global_tile("gA", &[256, 256]));
+    let g_b = Rc::new(BufBuilder::row_major_global_tile("gB", &[256, 256]));
+    let s_a = shared("sA", &[64, 32], DataType::Cutlasshalf);
+    let s_b = shared("sB", &[64, 64], DataType::Cutlasshalf);
+    let s_c = shared("sC", &[32, 32], DataType::Float32);
+    let r_c = Rc::new(BufBuilder::row_major_reg_tile("rC", &[32, 32]));
+    let r_d = Rc::new(BufBuilder::row_major_reg_tile("rD", &[32, 32]));
+
+    let edge = |src: &Rc<Buffer>, dst: &Rc<Buffer>| {
+        Rc::new(AttachedEdge::new(
+            src.clone(),
+            dst.clone(),
+            access(
+                std::slice::from_ref(&k),
+                vec![vec![vec![1]], vec![vec![0]]],
+                vec![vec![0], vec![0]],
+            ),
+        ))
+    };
+    let block_node = |block: ThrillerBlock| {
+        Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::Block(
+            Rc::new(block),
+        ))))
+    };
+
+    // gA -> sA loaded in 2 stages, rC -> sC, then gB -> sB and sC -> rD.
+    let mut producer = ThrillerBlock::new(
+        vec![edge(&g_a, &s_a)],
+        vec![edge(&r_c, &s_c)],
+        Rc::new(RefCell::new(ThrillerGraph::new())),
+        vec![k.clone()],
+    );
+    producer.set_stages(2);
+    let consumer = ThrillerBlock::new(
+        vec![edge(&g_b, &s_b), edge(&s_c, &r_d)],
+        vec![],
+        Rc::new(RefCell::new(ThrillerGraph::new())),
+        vec![k.clone()],
+    );
+
+    let nodes = vec![block_node(producer), block_node(consumer)];
+    let mut graph = ThrillerGraph::new();
+    graph.add_nodes(nodes.clone());
+    graph.add_edges(vec![Rc::new(ThrillerEdge::new(
+        nodes[0].clone(),
+        nodes[1].clone(),
+    ))]);
+    graph.connect();
+
+    // sA (2 x 4 KiB) and sB (8 KiB) are never live together, sC (4 KiB)
+    // is live in both blocks.
+    let mut pass = SharedMemoryPlanner::new(16 * 1024);
+    pass.run(&mut graph);
+
+    assert_eq!(pass.get_offset(&s_a), Some(0));
+    assert_eq!(pass.get_offset(&s_b), Some(0));
+    assert_eq!(pass.get_offset(&s_c), Some(8192));
+    assert_eq!(pass.get_offset(&r_c), None);
+    assert_eq!(pass.check().unwrap(), 12288);
+    assert_eq!(
+        pass.code(),
+        "auto* sA_ptr = reinterpret_cast<cutlass::half_t*>(shm + 0);\n\
+         auto* sC_ptr = reinterpret_cast<float*>(shm + 8192);\n\
+         auto* sB_ptr = reinterpret_cast<cutlass::half_t*>(shm + 0);\n"
+    );
+
+    let mut pass = SharedMemoryPlanner::new(8 * 1024);
+    pass.run(&mut graph);
+    assert!(matches!(
+        pass.check(),
+        Err(ThrillerError::SharedMemoryExceeded(12288, 8192))
+    ));
+
+    // A cyclic graph is reported instead of planning no buffers.
+    let mut graph = ThrillerGraph::new();
+    graph.add_nodes(nodes.clone());
+    graph.add_edges(vec![
+        Rc::new(ThrillerEdge::new(nodes[0].clone(), nodes[1].clone())),
+        Rc::new(ThrillerEdge::new(nodes[1].clone(), nodes[0].clone())),
+    ]);
+    graph.connect();
+
+    let mut pass = SharedMemoryPlanner::new(16 * 1024);
+    pass.run(&mut graph);
+    assert!(matches!(pass.check(), Err(ThrillerError::GraphCycle(_))));
+}
+
+#[test]
+fn test_nested_shared_memory_plan() {
+    let _guard = setup();
+
+    let n = fixed_ivar("n", 4);
+    let k = fixed_ivar("k", 8);
+
+    let g_a = Rc::new(BufBuilder::row_major_global_tile("gA", &[256, 256]));
+    let g_b = Rc::new(BufBuilder::row_major_global_tile("gB", &[256, 256]));
+    let g_x = Rc::new(BufBuilder::row_major_global_tile("gX", &[256, 256]));
+    let s_a = Rc::new(BufBuilder::row_major_shared_tile("sA", &[64, 32]));
+    let s_b = Rc::new(BufBuilder::row_major_shared_tile("sB", &[64, 32]));
+    let s_x = Rc::new(BufBuilder::row_major_shared_tile("sX", &[32, 32]));
+    let r_a = Rc::new(BufBuilder::row_major_reg_tile("rA", &[64, 32]));
+    let r_b = Rc::new(BufBuilder::row_major_reg_tile("rB", &[64, 32]));
+    let r_x = Rc::new(BufBuilder::row_major_reg_tile("rX", &[32, 32]));
+
+    let edge = |src: &Rc<Buffer>, dst: &Rc<Buffer>, ivar: &Rc<IterationVar>| {
+        Rc::new(AttachedEdge::new(
+            src.clone(),
+            dst.clone(),
+            access(
+                std::slice::from_ref(ivar),
+                vec![vec![vec![1]], vec![vec![0]]],
+                vec![vec![0], vec![0]],
+            ),
+        ))
+    };
+    let block_node = |block: ThrillerBlock| {
+        Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::Block(
+            Rc::new(block),
+        ))))
+    };
+
+    // n: gX -> sX, then k: gA -> sA -> rA, then k: gB -> sB -> rB, sX -> rX.
+    let first = block_node(ThrillerBlock::new(
+        vec![edge(&g_a, &s_a, &k), edge(&s_a, &r_a, &k)],
+        vec![],
+        Rc::new(RefCell::new(ThrillerGraph::new())),
+        vec![k.clone()],
+    ));
+    let second = block_node(ThrillerBlock::new(
+        vec![
+            edge(&g_b, &s_b, &k),
+            edge(&s_b, &r_b, &k),
+            edge(&s_x, &r_x, &k),
+        ],
+        vec![],
+        Rc::new(RefCell::new(ThrillerGraph::new())),
+        vec![k.clone()],
+    ));
+    let mut body = ThrillerGraph::new();
+    body.add_nodes(vec![first.clone(), second.clone()]);
+    body.add_edges(vec![Rc::new(ThrillerEdge::new(first, second))]);
+    body.connect();
+    let root = ThrillerBlock::new(
+        vec![edge(&g_x, &s_x, &n)],
+        vec![],
+        Rc::new(RefCell::new(body)),
+        vec![n],
+    );
+
+    // sA and sB are live in different loops of the same kernel loop and
+    // share their bytes, sX is loaded before them and read in the second.
+    let mut pass = SharedMemoryPlanner::new(16 * 1024);
+    pass.run(&mut block_graph(root.clone()));
+    assert_eq!(pass.get_offset(&s_a), Some(0));
+    assert_eq!(pass.get_offset(&s_b), Some(0));
+    assert_eq!(pass.get_offset(&s_x), Some(4096));
+    assert_eq!(pass.check().unwrap(), 6144);
+
+    // The engine places the shared tiles and reports the launch size.
+    let mut engine = ThrillerEngine::new(root);
+    assert_eq!(engine.shared_memory_size().unwrap(), 6144);
+    let code = engine.emit_dataflow("nested").unwrap();
+    assert!(code.contains("auto* sB_ptr = reinterpret_cast<cutlass::half_t*>(shm + 0);\n"));
+    assert!(code.contains("auto* sX_ptr = reinterpret_cast<cutlass::half_t*>(shm + 4096);\n"));
+
+    engine.set_shared_memory_limit(4096);
+    assert!(matches!(
+        engine.shared_memory_size(),
+        Err(ThrillerError::SharedMemoryExceeded(6144, 4096))
+    ));
+    assert!(engine.emit_dataflow("nested").is_err());
+}
+
+#[test]
+fn test_register_pressure() {
+    let _guard = setup();
+
+    let n = fixed_ivar("n", 4);
+    let k = fixed_ivar("k", 8);
+
+    let tile = |name: &str, typing: BufType, dims: &[usize], dtype: DataType| {
+        Rc::new(Buffer::new(name, typing, dims, Layout::RowMajor, dtype))
+    };
+    let g_q = tile(
+        "gQ",
+        BufType::GlobalTile,
+        &[256, 256],
+        DataType::Cutlasshalf,
+    );
+    let g_k = tile(
+        "gK",
+        BufType::GlobalTile,
+        &[256, 256],
+        DataType::Cutlasshalf,
+    );
+    let g_o = tile("gO", BufType::GlobalTile, &[256, 256], DataType::Float32);
+    let g_p = tile("gP", BufType::GlobalTile, &[256, 256], DataType::Float32);
+    // 128 threads: 16 + 16 + 32 + 64 registers per thread, and 8 for rP.
+    let r_q = tile("rQ", BufType::RegTile, &[64, 64], DataType::Cutlasshalf);
+    let r_k = tile("rK", BufType::RegTile, &[64, 64], DataType::Cutlasshalf);
+    let r_s = tile("rS", BufType::RegTile, &[64, 64], DataType::Float32);
+    let r_o = tile("rO", BufType::RegTile, &[64, 128], DataType::Float32);
+    let r_p = tile("rP", BufType::RegTile, &[32, 32], DataType::Float32);
+
+    let edge = |src: &Rc<Buffer>, dst: &Rc<Buffer>, ivar: &Rc<IterationVar>| {
+        Rc::new(AttachedEdge::new(
+            src.clone(),
+            dst.clone(),
+            access(
+                std::slice::from_ref(ivar),
+                vec![vec![vec![1]], vec![vec![0]]],
+                vec![vec![0], vec![0]],
+            ),
+        ))
+    };
+    let block_node = |block: ThrillerBlock| {
+        Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::Block(
+            Rc::new(block),
+        ))))
+    };
+
+    // for n: (for k: gQ -> rQ, gK -> rK, rS), rO -> gO
+    let mut inner_body = ThrillerGraph::new();
+    inner_body.add_nodes(vec![buffer_node((*r_s).clone())]);
+    let inner = ThrillerBlock::new(
+        vec![edge(&g_q, &r_q, &k), edge(&g_k, &r_k, &k)],
+        vec![],
+        Rc::new(RefCell::new(inner_body)),
+        vec![k.clone()],
+    );
+    let mut outer_body = ThrillerGraph::new();
+    outer_body.add_nodes(vec![block_node(inner)]);
+    let outer = ThrillerBlock::new(
+        vec![],
+        vec![edge(&r_o, &g_o, &n)],
+        Rc::new(RefCell::new(outer_body)),
+        vec![n.clone()],
+    );
+    // for n: gP -> rP
+    let epilogue = ThrillerBlock::new(
+        vec![edge(&g_p, &r_p, &n)],
+        vec![],
+        Rc::new(RefCell::new(ThrillerGraph::new())),
+        vec![n.clone()],
+    );
+
+    let mut graph = ThrillerGraph::new();
+    graph.add_nodes(vec![block_node(outer), block_node(epilogue)]);
+    graph.connect();
+
+    let pressure = RegisterPressure::new(&graph, 128, 255);
+    let usages = pressure
+        .get_usages()
+        .iter()
+        .map(|(_, usage)| *usage)
+        .collect::<Vec<_>>();
+    // rO stays live in the inner loop, rP is never live with the others.
+    assert_eq!(usages, vec![128, 128, 8]);
+    assert_eq!(pressure.check().unwrap(), 128);
+
+    let pressure = RegisterPressure::new(&graph, 128, 96);
+    assert!(matches!(
+        pressure.check(),
+        Err(ThrillerError::RegisterPressureExceeded(128, 96))
+    ));
+
+    // Fewer threads hold larger fragments each.
+    assert_eq!(RegisterPressure::new(&graph, 64, 255).max_usage(), 256);
+}
@@ -1,4 +1,4 @@
-use thriller_core::{Dimension, Layout, Shape};
+use thriller_core::{DataType, Dimension, Layout, Shape};
 
 #[test]
 fn test_strides() {
@@ -14,3 +14,30 @@ fn test_strides() {
 
     assert_eq!(strides_1.slice(), &[1, 2, 6]);
 }
+
+#[test]
+fn test_custom_strides() {
+    // A 64x64 tile padded to 72 elements per row.
+    let shape = Shape::with_strides(&[64, 64], &[72, 1]).unwrap();
+    assert_eq!(shape.get_strides().slice(), &[72, 1]);
+    assert_eq!(shape.num_elements(), 64 * 64);
+    assert_eq!(shape.size_in_bytes(DataType::Half), (63 * 72 + 64) * 2);
+    assert!(!shape.is_contiguous());
+
+    let shape = Shape::with_strides(&[2, 3, 4], &[1, 2, 6]).unwrap();
+    assert!(shape.is_contiguous());
+    assert_eq!(shape.size_in_bytes(DataType::Float32), 24 * 4);
+
+    // Overlapping strides.
+    assert!(Shape::with_strides(&[64, 64], &[32, 1]).is_err());
+    // Rank mismatch.
+    assert!(Shape::with_strides(&[64, 64], &[1]).is_err());
+}
+
+#[test]
+fn test_contiguous() {
+    let shape = Shape::new(&[16, 32], Layout::RowMajor);
+    assert_eq!(shape.num_elements(), 512);
+    assert_eq!(shape.size_in_bytes(DataType::Float32), 2048);
+    assert!(shape.is_contiguous());
+}
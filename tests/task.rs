@@ -0,0 +1,379 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use thriller_core::{
+    AccessMap, AccessMatrix, AccessOffset, BatchedGemm, BinaryOp, Broadcast, BufType, Buffer,
+    Combiner, Convert, DataType, Gemm, GroupedGemm, IterationBound, IterationVar, Layout, Map,
+    Reduce, Task, ThrillerEdge, ThrillerError, ThrillerGraph, ThrillerNode, ThrillerNodeInner,
+    TileCopy, UnaryOp,
+};
+
+use thriller_utils::{BufBuilder, OnlineSoftmax};
+
+mod common;
+use common::setup;
+
+#[test]
+fn test_map() {
+    let _guard = setup();
+
+    let r_x = Rc::new(BufBuilder::row_major_reg_tile("rX", &[16, 16]));
+    let r_bias = Rc::new(BufBuilder::row_major_reg_tile("rBias", &[16, 16]));
+    let r_y = Rc::new(BufBuilder::row_major_reg_tile("rY", &[16, 16]));
+
+    // Bias and activation epilogue.
+    let add = Map::binary(BinaryOp::Add, r_x.clone(), r_bias.clone(), r_y.clone()).unwrap();
+    assert_eq!(add.emit().unwrap(), "compute::add(rX, rBias, rY);\n");
+
+    let relu = ThrillerNode::new(ThrillerNodeInner::op(
+        Map::unary(UnaryOp::Relu, r_y.clone(), r_y.clone()).unwrap(),
+    ));
+    assert_eq!(relu.emit().unwrap(), "compute::relu(rY, rY);\n");
+
+    let scale = Map::unary(UnaryOp::Scale(0.125), r_x.clone(), r_y.clone()).unwrap();
+    assert_eq!(scale.emit().unwrap(), "compute::scale(rX, rY, 0.125);\n");
+
+    // Operands must be register buffers of the same shape.
+    let r_small = Rc::new(BufBuilder::row_major_reg_tile("rSmall", &[16, 8]));
+    assert!(matches!(
+        Map::binary(BinaryOp::Mul, r_x.clone(), r_small, r_y.clone()),
+        Err(ThrillerError::ShapeMismatch)
+    ));
+
+    let s_x = Rc::new(BufBuilder::row_major_shared_tile("sX", &[16, 16]));
+    assert!(matches!(
+        Map::unary(UnaryOp::Exp, s_x.clone(), r_y.clone()),
+        Err(ThrillerError::InvalidBufType)
+    ));
+
+    // Tile copies stay in registers.
+    let copy = TileCopy::new(r_x.clone(), r_y.clone());
+    assert_eq!(copy.emit().unwrap(), "copy_tile_r2r(rX, rY);\n");
+    assert!(matches!(
+        TileCopy::new(s_x, r_y.clone()).emit(),
+        Err(ThrillerError::InvalidBufType)
+    ));
+}
+
+#[test]
+fn test_reduce() {
+    let _guard = setup();
+
+    let r_s = Rc::new(BufBuilder::row_major_reg_tile("rS", &[16, 32]));
+    let r_max = Rc::new(BufBuilder::reg_vec("rMax", 16));
+    let r_sum = Rc::new(BufBuilder::reg_vec("rSum", 32));
+
+    let row_max = Reduce::new(r_s.clone(), r_max.clone(), 1, Combiner::Max).unwrap();
+    assert_eq!(row_max.emit().unwrap(), "compute::row_max(rS, rMax);\n");
+
+    let col_sum = Reduce::new(r_s.clone(), r_sum.clone(), 0, Combiner::Sum).unwrap();
+    assert_eq!(col_sum.emit().unwrap(), "compute::col_sum(rS, rSum);\n");
+
+    // One element per row is expected when reducing along axis 1.
+    assert!(matches!(
+        Reduce::new(r_s.clone(), r_sum.clone(), 1, Combiner::Sum),
+        Err(ThrillerError::ShapeMismatch)
+    ));
+    assert!(matches!(
+        Reduce::new(r_s.clone(), r_max.clone(), 2, Combiner::Max),
+        Err(ThrillerError::ShapeMismatch)
+    ));
+    assert!(matches!(
+        Reduce::new(r_s.clone(), r_s.clone(), 1, Combiner::Max),
+        Err(ThrillerError::InvalidBufType)
+    ));
+}
+
+#[test]
+fn test_broadcast() {
+    let _guard = setup();
+
+    let r_s = Rc::new(BufBuilder::row_major_reg_tile("rS", &[16, 32]));
+    let r_max = Rc::new(BufBuilder::reg_vec("rMax", 16));
+    let r_scale = Rc::new(BufBuilder::reg_vec("rScale", 32));
+
+    // Subtract the row maximum.
+    let sub = Broadcast::new(BinaryOp::Sub, r_s.clone(), r_max.clone(), r_s.clone(), 1).unwrap();
+    assert_eq!(
+        sub.emit().unwrap(),
+        "compute::broadcast_row_sub(rS, rMax, rS);\n"
+    );
+
+    let mul = Broadcast::new(BinaryOp::Mul, r_s.clone(), r_scale.clone(), r_s.clone(), 0).unwrap();
+    assert_eq!(
+        mul.emit().unwrap(),
+        "compute::broadcast_col_mul(rS, rScale, rS);\n"
+    );
+
+    assert!(matches!(
+        Broadcast::new(BinaryOp::Div, r_s.clone(), r_scale.clone(), r_s.clone(), 1),
+        Err(ThrillerError::ShapeMismatch)
+    ));
+    assert!(matches!(
+        Broadcast::new(BinaryOp::Div, r_s.clone(), r_s.clone(), r_s.clone(), 1),
+        Err(ThrillerError::InvalidBufType)
+    ));
+}
+
+#[test]
+fn test_online_softmax() {
+    let _guard = setup();
+
+    let r_s = Rc::new(BufBuilder::row_major_reg_tile("rS", &[16, 32]));
+    let r_v = Rc::new(BufBuilder::row_major_reg_tile("rV", &[32, 64]));
+    let r_o = Rc::new(BufBuilder::row_major_reg_tile("rO", &[16, 64]));
+    let r_m = Rc::new(BufBuilder::reg_vec("rM", 16));
+    let r_l = Rc::new(BufBuilder::reg_vec("rL", 16));
+
+    let softmax = OnlineSoftmax::new(r_s.clone(), r_m.clone(), r_l.clone(), r_o.clone()).unwrap();
+    let mut graph = ThrillerGraph::new();
+    softmax.build(&mut graph);
+
+    // O += P @ V
+    let node = |buf: &Rc<_>| {
+        Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::Buffer(
+            Rc::clone(buf),
+        ))))
+    };
+    let gemm = Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::op(
+        Gemm::new(
+            vec![node(softmax.get_probs()), node(&r_v)],
+            node(&r_o),
+            Rc::new(AccessMap::new(0, vec![])),
+        ),
+    ))));
+    graph.add_nodes(vec![gemm.clone()]);
+    graph.add_edges(vec![Rc::new(ThrillerEdge::new(
+        softmax.get_ops().last().unwrap().clone(),
+        gemm,
+    ))]);
+    graph.connect();
+
+    let code = graph.emit().unwrap();
+    let expected = "compute::row_max(rS, rS_max);\n\
+         compute::max(rM, rS_max, rS_new_max);\n\
+         compute::sub(rM, rS_new_max, rS_scale);\n\
+         compute::exp(rS_scale, rS_scale);\n\
+         compute::broadcast_row_sub(rS, rS_new_max, rS_probs);\n\
+         compute::exp(rS_probs, rS_probs);\n\
+         compute::row_sum(rS_probs, rS_sum);\n\
+         compute::mul(rL, rS_scale, rL);\n\
+         compute::add(rL, rS_sum, rL);\n\
+         compute::broadcast_row_mul(rO, rS_scale, rO);\n\
+         copy_vec_r2r(rS_new_max, rM);\n\
+         compute::gemm_(rS_probs, rV, rO);\n";
+    assert_eq!(code, expected);
+
+    let normalize = OnlineSoftmax::normalize(r_o.clone(), r_l.clone()).unwrap();
+    assert_eq!(
+        normalize.borrow().emit().unwrap(),
+        "compute::broadcast_row_div(rO, rL, rO);\n"
+    );
+
+    // The scores have one row per element of the running statistics.
+    let r_short = Rc::new(BufBuilder::reg_vec("rShort", 8));
+    assert!(OnlineSoftmax::new(r_s, r_short, r_l, r_o).is_err());
+}
+
+#[test]
+fn test_gemm() {
+    let _guard = setup();
+
+    let node = |name: &str| {
+        Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::Buffer(
+            Rc::new(BufBuilder::row_major_reg_tile(name, &[16, 16])),
+        ))))
+    };
+    let (r_a, r_b, r_c) = (node("rA"), node("rB"), node("rC"));
+
+    let i = Rc::new(IterationVar::new(
+        "i",
+        (IterationBound::Fixed(0), IterationBound::Fixed(4)),
+    ));
+    let j = Rc::new(IterationVar::new(
+        "j",
+        (IterationBound::Fixed(0), IterationBound::Fixed(2)),
+    ));
+
+    // A[i][j + 1], B[j][2], C[i].
+    let mut access_map = AccessMap::new(2, vec![2]);
+    access_map.add_iter_vars(vec![i.clone(), j.clone()]);
+    access_map.add_access_matrixs(vec![
+        AccessMatrix(vec![vec![1, 0], vec![0, 1]]),
+        AccessMatrix(vec![vec![0, 1], vec![0, 0]]),
+        AccessMatrix(vec![vec![1, 0]]),
+    ]);
+    access_map.add_access_offsets(vec![
+        AccessOffset(vec![0, 1]),
+        AccessOffset(vec![0, 2]),
+        AccessOffset(vec![0]),
+    ]);
+    let access_map = Rc::new(access_map);
+
+    let mut gemm = Gemm::new(
+        vec![r_a.clone(), r_b.clone()],
+        r_c.clone(),
+        access_map.clone(),
+    );
+    assert_eq!(
+        gemm.emit().unwrap(),
+        "compute::gemm_(rA[1 * i + 0][1 * j + 1], rB[1 * j + 0][2], rC[1 * i + 0]);\n"
+    );
+
+    gemm.set_transpose(false, true);
+    gemm.set_accumulate(false);
+    assert_eq!(
+        gemm.emit().unwrap(),
+        "compute::gemm_<false, true, false>(rA[1 * i + 0][1 * j + 1], rB[1 * j + 0][2], rC[1 * i + 0]);\n"
+    );
+
+    // One access matrix per operand.
+    let mut missing = AccessMap::new(1, vec![1]);
+    missing.add_iter_var(i.clone());
+    missing.add_access_matrixs(vec![
+        AccessMatrix(vec![vec![1]]),
+        AccessMatrix(vec![vec![1]]),
+    ]);
+    let gemm = Gemm::new(
+        vec![r_a.clone(), r_b.clone()],
+        r_c.clone(),
+        Rc::new(missing),
+    );
+    assert!(matches!(
+        gemm.emit(),
+        Err(ThrillerError::InvalidAccessPattern)
+    ));
+
+    // One column per iteration variable.
+    let mut columns = AccessMap::new(1, vec![1]);
+    columns.add_iter_var(i.clone());
+    columns.add_access_matrixs(vec![
+        AccessMatrix(vec![vec![1]]),
+        AccessMatrix(vec![vec![1, 0]]),
+        AccessMatrix(vec![vec![0]]),
+    ]);
+    let gemm = Gemm::new(vec![r_a, r_b], r_c, Rc::new(columns));
+    assert!(matches!(
+        gemm.emit(),
+        Err(ThrillerError::InvalidAccessPattern)
+    ));
+}
+
+#[test]
+fn test_batched_gemm() {
+    let _guard = setup();
+
+    let r_a = Rc::new(BufBuilder::row_major_reg_tile("rA", &[4, 16, 32]));
+    let r_b = Rc::new(BufBuilder::row_major_reg_tile("rB", &[4, 32, 8]));
+    let r_c = Rc::new(BufBuilder::row_major_reg_tile("rC", &[4, 16, 8]));
+
+    let batch = Rc::new(IterationVar::new(
+        "b",
+        (IterationBound::Fixed(0), IterationBound::Fixed(4)),
+    ));
+
+    let gemm = BatchedGemm::new(r_a.clone(), r_b.clone(), r_c.clone(), batch.clone()).unwrap();
+    assert_eq!(
+        gemm.emit().unwrap(),
+        "compute::gemm_(rA[1 * b + 0], rB[1 * b + 0], rC[1 * b + 0]);\n"
+    );
+
+    // The batch variable must stay within the leading dimension.
+    let overflow = Rc::new(IterationVar::new(
+        "b",
+        (IterationBound::Fixed(0), IterationBound::Fixed(8)),
+    ));
+    assert!(matches!(
+        BatchedGemm::new(r_a.clone(), r_b.clone(), r_c.clone(), overflow),
+        Err(ThrillerError::ShapeMismatch)
+    ));
+
+    // The inner dimensions of A and B must agree.
+    let r_bad = Rc::new(BufBuilder::row_major_reg_tile("rBad", &[4, 16, 8]));
+    assert!(matches!(
+        BatchedGemm::new(r_a.clone(), r_bad, r_c.clone(), batch.clone()),
+        Err(ThrillerError::ShapeMismatch)
+    ));
+
+    let r_flat = Rc::new(BufBuilder::row_major_reg_tile("rFlat", &[16, 32]));
+    assert!(matches!(
+        BatchedGemm::new(r_flat, r_b.clone(), r_c.clone(), batch.clone()),
+        Err(ThrillerError::ShapeMismatch)
+    ));
+
+    // Products are accumulated in a type holding them.
+    let tile = |name: &str, dims: &[usize], dtype: DataType| {
+        Rc::new(Buffer::new(
+            name,
+            BufType::RegTile,
+            dims,
+            Layout::RowMajor,
+            dtype,
+        ))
+    };
+    let r_a8 = tile("rA8", &[4, 16, 32], DataType::Int8);
+    let r_b8 = tile("rB8", &[4, 32, 8], DataType::Int8);
+    let r_c32 = tile("rC32", &[4, 16, 8], DataType::Int32);
+    let r_c16 = tile("rC16", &[4, 16, 8], DataType::Cutlasshalf);
+    assert!(BatchedGemm::new(r_a8.clone(), r_b8.clone(), r_c32.clone(), batch.clone()).is_ok());
+    assert!(matches!(
+        BatchedGemm::new(r_a8.clone(), r_b8.clone(), r_c16.clone(), batch),
+        Err(ThrillerError::DataTypeMismatch)
+    ));
+    assert!(matches!(
+        GroupedGemm::new(r_a8, r_b8, r_c16, vec![[16, 8, 32]; 4]),
+        Err(ThrillerError::DataTypeMismatch)
+    ));
+
+    let gemm = GroupedGemm::new(
+        r_a.clone(),
+        r_b.clone(),
+        r_c.clone(),
+        vec![[16, 8, 32], [16, 8, 16], [8, 8, 32], [16, 4, 32]],
+    )
+    .unwrap();
+    assert_eq!(
+        gemm.emit().unwrap(),
+        "compute::grouped_gemm_<16, 8, 32>(rA[0], rB[0], rC[0]);\n\
+         compute::grouped_gemm_<16, 8, 16>(rA[1], rB[1], rC[1]);\n\
+         compute::grouped_gemm_<8, 8, 32>(rA[2], rB[2], rC[2]);\n\
+         compute::grouped_gemm_<16, 4, 32>(rA[3], rB[3], rC[3]);\n"
+    );
+
+    // One shape per group, each fitting into the operand tiles.
+    assert!(matches!(
+        GroupedGemm::new(r_a.clone(), r_b.clone(), r_c.clone(), vec![[16, 8, 32]]),
+        Err(ThrillerError::ShapeMismatch)
+    ));
+    assert!(matches!(
+        GroupedGemm::new(r_a, r_b, r_c, vec![[32, 8, 32]; 4]),
+        Err(ThrillerError::ShapeMismatch)
+    ));
+}
+
+#[test]
+fn test_element_types() {
+    let _guard = setup();
+
+    let tile = |name: &str, dtype: DataType| {
+        Rc::new(Buffer::new(
+            name,
+            BufType::RegTile,
+            &[16, 16],
+            Layout::RowMajor,
+            dtype,
+        ))
+    };
+    let r_acc = tile("rAcc", DataType::Float32);
+    let r_half = tile("rHalf", DataType::Cutlasshalf);
+
+    // The cast types are those of the buffers.
+    let cast = Convert::new(r_acc.clone(), r_half.clone());
+    assert_eq!(cast.emit().unwrap(), "cast_f32_to_f16(rAcc, rHalf);\n");
+
+    assert!(matches!(
+        Map::binary(BinaryOp::Add, r_acc.clone(), r_half.clone(), r_acc.clone()),
+        Err(ThrillerError::DataTypeMismatch)
+    ));
+    assert!(Map::binary(BinaryOp::Add, r_acc.clone(), r_acc.clone(), r_acc).is_ok());
+}
@@ -54,8 +54,10 @@ if __name__ == '__main__':
     rB = Tensor("rB", RegDimB, RegLayoutB, TensorType.RegTile)
     rC = Tensor("rC", RegDimC, RegLayoutC, TensorType.RegTile)
     rD = Tensor("rD", RegDimD, RegLayoutD, TensorType.RegTile)
-    rAcc = Tensor("rAcc", RegDimAcc, RegLayoutAcc, TensorType.RegTile)
-    rAccHalf = Tensor("rAccHalf", RegDimAcc, RegLayoutAcc, TensorType.RegTile)
+    rAcc = Tensor("rAcc", RegDimAcc, RegLayoutAcc,
+                  TensorType.RegTile, dtype=DType.F32)
+    rAccHalf = Tensor("rAccHalf", RegDimAcc, RegLayoutAcc,
+                      TensorType.RegTile, dtype=DType.CutlassHalf)
 
     # Define Shared Tensor for A, B, C, D.
     sA = Tensor("sA", SharedDimA, SharedLayoutA, TensorType.SharedTile)
@@ -83,7 +85,7 @@ if __name__ == '__main__':
     RegAccCGemmDNode = Node.gemm(NodeRAccHalf, NodeRC, NodeRD)
 
     # Build Cast Node for Acc -> Half
-    AccCastNode = Node.cast(rAcc, rAccHalf, DType.F32, DType.Half)
+    AccCastNode = Node.cast(rAcc, rAccHalf)
 
     # Define Shared Node for A, B, C, D.
     NodeSA = Node.tensor(sA)
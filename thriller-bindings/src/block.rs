@@ -15,11 +15,13 @@ pub struct PyAttachedEdge(pub Rc<AttachedEdge>);
 #[pymethods]
 impl PyBlock {
     #[new]
+    #[pyo3(signature = (inputs, outputs, subgraph, ivars, stages=1))]
     fn new(
         inputs: &Bound<PyList>,
         outputs: &Bound<PyList>,
         subgraph: PyRef<PyGraph>,
         ivars: &Bound<PyList>,
+        stages: usize,
     ) -> PyResult<Self> {
         let inputs = inputs
             .into_iter()
@@ -50,7 +52,8 @@ impl PyBlock {
 
         let subgraph = Rc::clone(&subgraph.0);
 
-        let block = ThrillerBlock::new(inputs, outputs, subgraph, ivars);
+        let mut block = ThrillerBlock::new(inputs, outputs, subgraph, ivars);
+        block.set_stages(stages);
 
         Ok(PyBlock(Rc::new(block)))
     }
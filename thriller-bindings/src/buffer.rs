@@ -1,7 +1,9 @@
 use std::rc::Rc;
 
 use pyo3::prelude::*;
-use thriller_core::{BufType, Buffer, Dim, Layout};
+use thriller_core::{BufType, Buffer, DataType, Dim, Layout, Swizzle};
+
+use crate::dtype::PyDType;
 
 #[pyclass(unsendable, module = "buffer", name = "Tensor")]
 pub struct PyBuffer(pub Rc<Buffer>);
@@ -10,6 +12,7 @@ pub struct PyBuffer(pub Rc<Buffer>);
 pub enum PyLayout {
     RowMajor,
     ColMajor,
+    Swizzle,
 }
 
 #[pyclass(module = "buffer", name = "TensorType")]
@@ -23,10 +26,20 @@ pub enum PyBufType {
 #[pymethods]
 impl PyBuffer {
     #[new]
-    fn new(name: String, dim: Vec<usize>, py_layout: &PyLayout, py_buf_type: &PyBufType) -> Self {
+    #[pyo3(signature = (name, dim, py_layout, py_buf_type, swizzle=(3, 3, 3), dtype=None))]
+    fn new(
+        name: String,
+        dim: Vec<usize>,
+        py_layout: &PyLayout,
+        py_buf_type: &PyBufType,
+        swizzle: (usize, usize, usize),
+        dtype: Option<PyRef<PyDType>>,
+    ) -> Self {
         let layout: Layout<Dim> = match py_layout {
             PyLayout::RowMajor => Layout::RowMajor,
             PyLayout::ColMajor => Layout::ColumnMajor,
+            // `swizzle` is `(B, M, S)` as in CuTe's `Swizzle<B, M, S>`.
+            PyLayout::Swizzle => Layout::Swizzle(Swizzle::new(swizzle.0, swizzle.1, swizzle.2)),
         };
 
         let buf_type = match py_buf_type {
@@ -36,15 +49,24 @@ impl PyBuffer {
             PyBufType::RegVec => BufType::RegVec,
         };
 
-        Self(Rc::new(Buffer::new(name.as_str(), buf_type, &dim, layout)))
+        let dtype = dtype.map_or_else(DataType::default, |dtype| DataType::from(&*dtype));
+
+        Self(Rc::new(Buffer::new(
+            name.as_str(),
+            buf_type,
+            &dim,
+            layout,
+            dtype,
+        )))
     }
 
     fn __str__(&self) -> PyResult<String> {
         Ok(format!(
-            "[name: {}, shape: {:?}, type: {:?}]",
+            "[name: {}, shape: {:?}, type: {:?}, dtype: {}]",
             self.0.get_name(),
             self.0.get_shape(),
-            self.0.get_typing()
+            self.0.get_typing(),
+            self.0.get_dtype()
         ))
     }
 
@@ -1,4 +1,5 @@
 use pyo3::prelude::*;
+use thriller_core::DataType;
 
 #[pyclass(module = "dtype", name = "DType")]
 pub enum PyDType {
@@ -6,4 +7,27 @@ pub enum PyDType {
     F64,
     Half,
     CutlassHalf,
+    BF16,
+    TF32,
+    F8E4M3,
+    F8E5M2,
+    I8,
+    I32,
+}
+
+impl From<&PyDType> for DataType {
+    fn from(dtype: &PyDType) -> Self {
+        match dtype {
+            PyDType::F32 => DataType::Float32,
+            PyDType::F64 => DataType::Float64,
+            PyDType::Half => DataType::Half,
+            PyDType::CutlassHalf => DataType::Cutlasshalf,
+            PyDType::BF16 => DataType::BF16,
+            PyDType::TF32 => DataType::TF32,
+            PyDType::F8E4M3 => DataType::Float8E4M3,
+            PyDType::F8E5M2 => DataType::Float8E5M2,
+            PyDType::I8 => DataType::Int8,
+            PyDType::I32 => DataType::Int32,
+        }
+    }
 }
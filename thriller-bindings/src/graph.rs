@@ -2,12 +2,15 @@ use pyo3::prelude::*;
 use pyo3::types::PyList;
 
 use thriller_core::{
-    AccessMap, AllocateEdge, AllocateVar, Convert, DataType, Gemm, GraphPass, Task, ThrillerEdge,
-    ThrillerGraph, ThrillerNode, ThrillerNodeInner,
+    AccessMap, AllocateEdge, AllocateVar, BarrierElimination, BatchedGemm, BinaryOp, Broadcast,
+    Combiner, Convert, Gemm, GraphPass, GroupedGemm, LoopFusion, LoopInterchange, LoopTiling, Map,
+    Reduce, RegisterPressure, ShapeValidation, SharedMemoryPlanner, Task, ThrillerEdge,
+    ThrillerGraph, ThrillerNode, ThrillerNodeInner, UnaryOp,
 };
 
+use crate::block::PyBlock;
 use crate::buffer::PyBuffer;
-use crate::{block::PyBlock, dtype::PyDType};
+use crate::var::PyIterationVar;
 
 use std::{cell::RefCell, rc::Rc};
 
@@ -67,6 +70,65 @@ impl PyGraph {
         Ok(pass.code().clone())
     }
 
+    fn interchange_loops(&mut self, order: Vec<String>) -> PyResult<usize> {
+        let mut graph = self.0.borrow_mut();
+        let order = order.iter().map(|name| name.as_str()).collect::<Vec<_>>();
+        let mut pass = LoopInterchange::new(&order);
+        pass.run(&mut graph);
+        Ok(pass.interchanged().len())
+    }
+
+    fn fuse_loops(&mut self) -> PyResult<usize> {
+        let mut graph = self.0.borrow_mut();
+        let mut pass = LoopFusion::new();
+        pass.run(&mut graph);
+        Ok(pass.fused().len())
+    }
+
+    fn tile_loop(&mut self, ivar: &str, factor: usize) -> PyResult<usize> {
+        let mut graph = self.0.borrow_mut();
+        let mut pass = LoopTiling::new(ivar, factor);
+        pass.run(&mut graph);
+        Ok(pass.tiled().len())
+    }
+
+    fn eliminate_barriers(&mut self) -> PyResult<usize> {
+        let mut graph = self.0.borrow_mut();
+        let mut pass = BarrierElimination::new();
+        pass.run(&mut graph);
+        pass.check()
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("{:?}", e)))
+    }
+
+    #[pyo3(signature = (limit=48 * 1024))]
+    fn plan_shared_memory(&mut self, limit: usize) -> PyResult<(String, usize)> {
+        let mut graph = self.0.borrow_mut();
+        let mut pass = SharedMemoryPlanner::new(limit);
+        pass.run(&mut graph);
+        let total = pass
+            .check()
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("{:?}", e)))?;
+        Ok((pass.code(), total))
+    }
+
+    #[pyo3(signature = (threads, limit=255))]
+    fn estimate_registers(&self, threads: usize, limit: usize) -> PyResult<usize> {
+        RegisterPressure::new(&self.0.borrow(), threads, limit)
+            .check()
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("{:?}", e)))
+    }
+
+    fn validate(&mut self) -> PyResult<Vec<String>> {
+        let mut graph = self.0.borrow_mut();
+        let mut pass = ShapeValidation::new();
+        pass.run(&mut graph);
+        Ok(pass
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect())
+    }
+
     fn codegen(&self) -> PyResult<String> {
         self.0
             .borrow()
@@ -75,6 +137,18 @@ impl PyGraph {
     }
 }
 
+fn parse_binary_op(op: &str) -> Option<BinaryOp> {
+    match op {
+        "add" => Some(BinaryOp::Add),
+        "sub" => Some(BinaryOp::Sub),
+        "mul" => Some(BinaryOp::Mul),
+        "div" => Some(BinaryOp::Div),
+        "max" => Some(BinaryOp::Max),
+        "min" => Some(BinaryOp::Min),
+        _ => None,
+    }
+}
+
 #[pyclass(unsendable, module = "graph", name = "Node")]
 pub struct PyNode(pub Rc<RefCell<ThrillerNode>>);
 
@@ -93,14 +167,24 @@ impl PyNode {
     }
 
     #[staticmethod]
-    fn gemm(a: PyRef<PyNode>, b: PyRef<PyNode>, c: PyRef<PyNode>) -> Self {
+    #[pyo3(signature = (a, b, c, transpose_a=false, transpose_b=false, accumulate=true))]
+    fn gemm(
+        a: PyRef<PyNode>,
+        b: PyRef<PyNode>,
+        c: PyRef<PyNode>,
+        transpose_a: bool,
+        transpose_b: bool,
+        accumulate: bool,
+    ) -> Self {
         let access_map = AccessMap::new(0, vec![]);
 
         let node_a = Rc::clone(&a.0);
         let node_b = Rc::clone(&b.0);
         let node_c = Rc::clone(&c.0);
 
-        let gemm = Gemm::new(vec![node_a, node_b], node_c, Rc::new(access_map));
+        let mut gemm = Gemm::new(vec![node_a, node_b], node_c, Rc::new(access_map));
+        gemm.set_transpose(transpose_a, transpose_b);
+        gemm.set_accumulate(accumulate);
 
         let node = ThrillerNode::new(ThrillerNodeInner::Op(Box::new(gemm)));
 
@@ -108,35 +192,142 @@ impl PyNode {
     }
 
     #[staticmethod]
-    fn cast(
-        src: PyRef<PyBuffer>,
-        dst: PyRef<PyBuffer>,
-        sdtype: PyRef<PyDType>,
-        ddtype: PyRef<PyDType>,
-    ) -> Self {
-        let sdtype = match *sdtype {
-            PyDType::F32 => DataType::Float32,
-            PyDType::F64 => DataType::Float64,
-            PyDType::Half => DataType::Half,
-            PyDType::CutlassHalf => DataType::Cutlasshalf,
-        };
+    fn batched_gemm(
+        a: PyRef<PyBuffer>,
+        b: PyRef<PyBuffer>,
+        c: PyRef<PyBuffer>,
+        batch: PyRef<PyIterationVar>,
+    ) -> PyResult<Self> {
+        let gemm = BatchedGemm::new(
+            Rc::clone(&a.0),
+            Rc::clone(&b.0),
+            Rc::clone(&c.0),
+            Rc::clone(&batch.0),
+        )
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("{:?}", e)))?;
 
-        let ddtype = match *ddtype {
-            PyDType::F32 => DataType::Float32,
-            PyDType::F64 => DataType::Float64,
-            PyDType::Half => DataType::Half,
-            PyDType::CutlassHalf => DataType::Cutlasshalf,
-        };
+        let node = ThrillerNode::new(ThrillerNodeInner::op(gemm));
+        Ok(PyNode(Rc::new(RefCell::new(node))))
+    }
+
+    #[staticmethod]
+    fn grouped_gemm(
+        a: PyRef<PyBuffer>,
+        b: PyRef<PyBuffer>,
+        c: PyRef<PyBuffer>,
+        shapes: Vec<[usize; 3]>,
+    ) -> PyResult<Self> {
+        let gemm = GroupedGemm::new(Rc::clone(&a.0), Rc::clone(&b.0), Rc::clone(&c.0), shapes)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("{:?}", e)))?;
+
+        let node = ThrillerNode::new(ThrillerNodeInner::op(gemm));
+        Ok(PyNode(Rc::new(RefCell::new(node))))
+    }
 
+    #[staticmethod]
+    fn cast(src: PyRef<PyBuffer>, dst: PyRef<PyBuffer>) -> Self {
         let sbuf = Rc::clone(&src.0);
         let dbuf = Rc::clone(&dst.0);
 
-        let cast = Convert::new(sbuf, dbuf, sdtype, ddtype);
+        let cast = Convert::new(sbuf, dbuf);
         let node = ThrillerNode::new(ThrillerNodeInner::Op(Box::new(cast)));
 
         PyNode(Rc::new(RefCell::new(node)))
     }
 
+    #[staticmethod]
+    #[pyo3(signature = (op, inputs, output, factor=1.0))]
+    fn map(
+        op: &str,
+        inputs: Vec<PyRef<PyBuffer>>,
+        output: PyRef<PyBuffer>,
+        factor: f32,
+    ) -> PyResult<Self> {
+        let unary = match op {
+            "exp" => Some(UnaryOp::Exp),
+            "log" => Some(UnaryOp::Log),
+            "neg" => Some(UnaryOp::Neg),
+            "relu" => Some(UnaryOp::Relu),
+            "gelu" => Some(UnaryOp::Gelu),
+            "scale" => Some(UnaryOp::Scale(factor)),
+            _ => None,
+        };
+        let binary = parse_binary_op(op);
+
+        let inputs = inputs
+            .iter()
+            .map(|buf| Rc::clone(&buf.0))
+            .collect::<Vec<_>>();
+        let output = Rc::clone(&output.0);
+
+        let map = match (unary, binary, inputs.as_slice()) {
+            (Some(op), _, [src]) => Map::unary(op, src.clone(), output),
+            (_, Some(op), [lhs, rhs]) => Map::binary(op, lhs.clone(), rhs.clone(), output),
+            _ => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "Invalid map operator {} with {} inputs",
+                    op,
+                    inputs.len()
+                )))
+            }
+        }
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("{:?}", e)))?;
+
+        let node = ThrillerNode::new(ThrillerNodeInner::op(map));
+        Ok(PyNode(Rc::new(RefCell::new(node))))
+    }
+
+    #[staticmethod]
+    fn broadcast(
+        op: &str,
+        tile: PyRef<PyBuffer>,
+        vec: PyRef<PyBuffer>,
+        dst: PyRef<PyBuffer>,
+        axis: usize,
+    ) -> PyResult<Self> {
+        let op = parse_binary_op(op).ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err(format!("Invalid broadcast operator {}", op))
+        })?;
+
+        let broadcast = Broadcast::new(
+            op,
+            Rc::clone(&tile.0),
+            Rc::clone(&vec.0),
+            Rc::clone(&dst.0),
+            axis,
+        )
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("{:?}", e)))?;
+
+        let node = ThrillerNode::new(ThrillerNodeInner::op(broadcast));
+        Ok(PyNode(Rc::new(RefCell::new(node))))
+    }
+
+    #[staticmethod]
+    fn reduce(
+        src: PyRef<PyBuffer>,
+        dst: PyRef<PyBuffer>,
+        axis: usize,
+        combiner: &str,
+    ) -> PyResult<Self> {
+        let combiner = match combiner {
+            "sum" => Combiner::Sum,
+            "max" => Combiner::Max,
+            "min" => Combiner::Min,
+            _ => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "Invalid combiner {}",
+                    combiner
+                )))
+            }
+        };
+
+        let reduce = Reduce::new(Rc::clone(&src.0), Rc::clone(&dst.0), axis, combiner)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("{:?}", e)))?;
+
+        let node = ThrillerNode::new(ThrillerNodeInner::op(reduce));
+        Ok(PyNode(Rc::new(RefCell::new(node))))
+    }
+
     fn codegen(&self) -> PyResult<String> {
         let node = self.0.borrow();
         node.emit()
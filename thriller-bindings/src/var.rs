@@ -1,6 +1,6 @@
 use std::rc::Rc;
 
-use thriller_core::{IterationBound, IterationVar};
+use thriller_core::{IterationBound, IterationVar, RegularVar};
 
 use pyo3::prelude::*;
 use pyo3::types::PyTuple;
@@ -12,12 +12,17 @@ pub struct PyIterationVar(pub Rc<IterationVar>);
 impl PyIterationVar {
     #[new]
     fn new(name: String, domain: &Bound<'_, PyTuple>) -> Self {
-        let domain = domain.extract::<(usize, usize)>().unwrap();
-        let domain_bound = (
-            IterationBound::Fixed(domain.0),
-            IterationBound::Fixed(domain.1),
-        );
+        let domain = domain.extract::<(Bound<PyAny>, Bound<PyAny>)>().unwrap();
+        let domain_bound = (extract_bound(&domain.0), extract_bound(&domain.1));
         let var = IterationVar::new(&name, domain_bound);
         PyIterationVar(Rc::new(var))
     }
 }
+
+/// Extract an [`IterationBound`] from either an integer or a symbolic name.
+fn extract_bound(bound: &Bound<PyAny>) -> IterationBound {
+    match bound.extract::<usize>() {
+        Ok(value) => IterationBound::Fixed(value),
+        Err(_) => IterationBound::Var(RegularVar::new(bound.extract::<String>().unwrap())),
+    }
+}
@@ -1,11 +1,13 @@
 use std::rc::Rc;
 
-use crate::{var::IterationVar, ThrillerResult, Var};
+use crate::{var::IterationVar, ThrillerError, ThrillerResult, Var};
 
 /// An [`AccessMatrix`] represents a multi-dimensional access pattern.
+#[derive(Clone)]
 pub struct AccessMatrix(pub Vec<Vec<usize>>);
 
 /// An [`AccessOffset`] represents a multi-dimensional access pattern.
+#[derive(Clone)]
 pub struct AccessOffset(pub Vec<usize>);
 
 /// An [`AccessMap`] represents a multi-dimensional access pattern.
@@ -15,6 +17,7 @@ pub struct AccessOffset(pub Vec<usize>);
 /// and the target [`crate::Buffer`].
 ///
 /// It refers from polyhedral mathematical model for analyzing memory access patterns.
+#[derive(Clone)]
 pub struct AccessMap {
     pub(crate) loop_depth: usize,
     #[allow(dead_code)]
@@ -86,21 +89,119 @@ impl AccessMap {
         self.loop_depth
     }
 
+    /// Reorder the iteration variables of the access map, permuting the
+    /// columns of every access matrix accordingly. `order[i]` is the current
+    /// column of the iteration variable that becomes column `i`.
+    pub(crate) fn permute_ivars(&self, order: &[usize]) -> AccessMap {
+        let mut access = self.clone();
+
+        access.ivars = order.iter().map(|&col| self.ivars[col].clone()).collect();
+        for matrix in access.access_matrixs.iter_mut() {
+            for row in matrix.0.iter_mut() {
+                *row = order
+                    .iter()
+                    .map(|&col| row.get(col).copied().unwrap_or(0))
+                    .collect();
+            }
+        }
+
+        access
+    }
+
+    /// Replace the iteration variables `from[i]` by `to[i]`.
+    pub(crate) fn substitute_ivars(
+        &self,
+        from: &[Rc<IterationVar>],
+        to: &[Rc<IterationVar>],
+    ) -> AccessMap {
+        let mut access = self.clone();
+
+        for ivar in access.ivars.iter_mut() {
+            if let Some(index) = from.iter().position(|var| var.get_id() == ivar.get_id()) {
+                *ivar = to[index].clone();
+            }
+        }
+
+        access
+    }
+
+    /// Split the iteration variable `ivar` into `outer` and `inner`, where
+    /// `ivar = lower + factor * outer + inner`. Every coefficient `a` of
+    /// `ivar` becomes `a * factor` for `outer` and `a` for `inner`, and
+    /// `a * lower` is added to the offset.
+    pub(crate) fn split_ivar(
+        &self,
+        ivar: &IterationVar,
+        lower: usize,
+        factor: usize,
+        outer: Rc<IterationVar>,
+        inner: Rc<IterationVar>,
+    ) -> AccessMap {
+        let Some(col) = self.ivars.iter().position(|v| v.get_id() == ivar.get_id()) else {
+            return self.clone();
+        };
+
+        let mut access = self.clone();
+        access.ivars.splice(col..=col, [outer, inner]);
+        access.loop_depth += 1;
+
+        for (index, matrix) in access.access_matrixs.iter_mut().enumerate() {
+            for (rindex, row) in matrix.0.iter_mut().enumerate() {
+                let coef = row.get(col).copied().unwrap_or(0);
+                if col < row.len() {
+                    row.splice(col..=col, [coef * factor, coef]);
+                }
+
+                if coef != 0 && lower != 0 {
+                    while access.offset.len() <= index {
+                        access.offset.push(AccessOffset(vec![]));
+                    }
+                    let offset = &mut access.offset[index].0;
+                    offset.resize(offset.len().max(rindex + 1), 0);
+                    offset[rindex] += coef * lower;
+                }
+            }
+        }
+
+        access
+    }
+
     /// Emit Memory Access code based on index.
+    ///
+    /// Each row of the access matrix yields the index of one dimension.
+    /// A missing offset is treated as zero.
     pub fn emit_access(&self, index: usize) -> ThrillerResult<Vec<String>> {
+        self.emit_access_with(index, &|ivar| ivar.get_name().clone())
+    }
+
+    /// Emit Memory Access code based on index, with each iteration variable
+    /// replaced by the expression given by `ivar_code`.
+    pub(crate) fn emit_access_with(
+        &self,
+        index: usize,
+        ivar_code: &dyn Fn(&IterationVar) -> String,
+    ) -> ThrillerResult<Vec<String>> {
         let mut access = vec![];
 
-        let access_matrix = &self.access_matrixs[index];
-        let access_offset = &self.offset[index];
+        let access_matrix = self
+            .access_matrixs
+            .get(index)
+            .ok_or(ThrillerError::MissingAccessMap)?;
+        let access_offset = self.offset.get(index);
         let ivars = &self.ivars;
 
         for (rindex, access_row) in access_matrix.0.iter().enumerate() {
-            let offset = &access_offset.0[rindex];
+            let offset = access_offset
+                .and_then(|offset| offset.0.get(rindex))
+                .copied()
+                .unwrap_or(0);
 
             let mut code = String::new();
             // Emit the access row mulipled ivar.
             for (cindex, access_col) in access_row.iter().enumerate() {
-                let ivar = &ivars[cindex];
+                let ivar = ivars
+                    .get(cindex)
+                    .ok_or(ThrillerError::InvalidAccessPattern)?;
                 // Emit the access row mulipled ivar.
                 if cindex != 0 {
                     code.push_str(" + ");
@@ -109,13 +210,15 @@ impl AccessMap {
                     format!(
                         "{access} * {ivar}",
                         access = *access_col,
-                        ivar = ivar.get_name()
+                        ivar = ivar_code(ivar)
                     )
                     .as_str(),
                 );
             }
 
-            if *offset != 0 {
+            if code.is_empty() {
+                code = offset.to_string();
+            } else if offset != 0 {
                 code.push_str(format!(" + {}", offset).as_str());
             }
 
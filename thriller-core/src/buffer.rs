@@ -1,5 +1,5 @@
 use crate::shape::Ix;
-use crate::{next_id, Dim, Layout, Shape};
+use crate::{next_id, DataType, Dim, Layout, MemoryLevel, Shape};
 
 /// Buffer type.
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -14,8 +14,19 @@ pub enum BufType {
     RegVec,
 }
 
+impl BufType {
+    /// Get the [`MemoryLevel`] the buffer lives in.
+    pub fn get_memory_level(&self) -> MemoryLevel {
+        match self {
+            BufType::GlobalTile => MemoryLevel::Global,
+            BufType::SharedTile => MemoryLevel::Shared,
+            BufType::RegTile | BufType::RegVec => MemoryLevel::Register,
+        }
+    }
+}
+
 /// [`Buffer`] represents an addressable instance declared in user mode, which contains
-/// [`Shape`], [`Layout`] and [`BufType`].
+/// [`Shape`], [`Layout`], [`BufType`] and the [`DataType`] of its elements.
 #[allow(dead_code)]
 #[derive(Clone, Debug)]
 pub struct Buffer {
@@ -23,17 +34,38 @@ pub struct Buffer {
     id: usize,
     typing: BufType,
     shape: Shape,
+    dtype: DataType,
 }
 
 impl Buffer {
     /// Create a new Buffer with the given name.
-    pub fn new(name: &str, typing: BufType, dim: &[Ix], layout: Layout<Dim>) -> Self {
+    pub fn new(
+        name: &str,
+        typing: BufType,
+        dim: &[Ix],
+        layout: Layout<Dim>,
+        dtype: DataType,
+    ) -> Self {
         let id = next_id();
         Buffer {
             name: name.to_string(),
             id,
             typing,
             shape: Shape::new(dim, layout),
+            dtype,
+        }
+    }
+
+    /// Create a new Buffer with the given name and an already built [`Shape`],
+    /// e.g. one with explicit strides from [`Shape::with_strides`].
+    pub fn with_shape(name: &str, typing: BufType, shape: Shape, dtype: DataType) -> Self {
+        let id = next_id();
+        Buffer {
+            name: name.to_string(),
+            id,
+            typing,
+            shape,
+            dtype,
         }
     }
 
@@ -56,4 +88,9 @@ impl Buffer {
     pub fn get_typing(&self) -> &BufType {
         &self.typing
     }
+
+    /// Get Buffer element type.
+    pub fn get_dtype(&self) -> DataType {
+        self.dtype
+    }
 }
@@ -0,0 +1,411 @@
+use std::rc::Rc;
+
+use crate::{
+    AccessMap, AttachedEdge, Buffer, IterationBound, IterationVar, ThrillerBlock,
+    ThrillerNodeInner, Var,
+};
+
+/// Whether a [`MemoryAccess`] reads or writes its buffer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccessKind {
+    /// The buffer is read.
+    Read,
+    /// The buffer is written.
+    Write,
+}
+
+/// A [`MemoryAccess`] is one access to a [`Buffer`], described by one access
+/// matrix and offset of an [`AccessMap`].
+#[derive(Clone)]
+pub struct MemoryAccess {
+    buffer: Rc<Buffer>,
+    access: Rc<AccessMap>,
+    index: usize,
+    kind: AccessKind,
+}
+
+impl MemoryAccess {
+    /// Create a new access to `buffer` through the access matrix at `index`
+    /// in `access`, e.g. one of the operands of an op access map.
+    pub fn new(buffer: Rc<Buffer>, access: Rc<AccessMap>, index: usize, kind: AccessKind) -> Self {
+        MemoryAccess {
+            buffer,
+            access,
+            index,
+            kind,
+        }
+    }
+
+    /// The read of the source buffer of an [`AttachedEdge`].
+    pub fn source_of(edge: &AttachedEdge) -> Self {
+        Self::new(edge.src.clone(), edge.access.clone(), 0, AccessKind::Read)
+    }
+
+    /// The write of the destination buffer of an [`AttachedEdge`].
+    pub fn target_of(edge: &AttachedEdge) -> Self {
+        Self::new(edge.dst.clone(), edge.access.clone(), 1, AccessKind::Write)
+    }
+
+    /// Get the accessed buffer.
+    pub fn get_buffer(&self) -> &Rc<Buffer> {
+        &self.buffer
+    }
+
+    /// Get the access kind.
+    pub fn get_kind(&self) -> AccessKind {
+        self.kind
+    }
+
+    /// The access function with one matrix column per iteration variable
+    /// in `ivars`. `None` if the access map does not describe this access.
+    fn aligned(&self, ivars: &[Rc<IterationVar>]) -> Option<AlignedAccess> {
+        let matrix = self.access.get_access_matrixs().get(self.index)?;
+        let offset = self.access.get_access_offsets().get(self.index);
+        let access_ivars = self.access.get_iter_vars();
+
+        let mut rows = vec![];
+        let mut offsets = vec![];
+        let mut outer = vec![];
+
+        for (rindex, access_row) in matrix.0.iter().enumerate() {
+            let mut row = vec![0; ivars.len()];
+            let mut outer_row = vec![];
+
+            for (cindex, &coef) in access_row.iter().enumerate() {
+                let ivar = access_ivars.get(cindex)?;
+                match ivars.iter().position(|v| v.get_id() == ivar.get_id()) {
+                    Some(column) => row[column] += coef as isize,
+                    None if coef != 0 => outer_row.push((ivar.get_id(), coef as isize)),
+                    None => {}
+                }
+            }
+
+            rows.push(row);
+            offsets.push(
+                offset
+                    .and_then(|offset| offset.0.get(rindex))
+                    .map_or(0, |&offset| offset as isize),
+            );
+            outer.push(outer_row);
+        }
+
+        Some(AlignedAccess {
+            matrix: rows,
+            offset: offsets,
+            outer,
+        })
+    }
+}
+
+/// An access function `A * i + o` aligned to the iteration variables of a
+/// loop nest.
+struct AlignedAccess {
+    matrix: Vec<Vec<isize>>,
+    offset: Vec<isize>,
+    /// Per row, the `(id, coef)` of iteration variables of outer loops,
+    /// which are constant within the loop nest.
+    outer: Vec<Vec<(usize, isize)>>,
+}
+
+/// Kind of a data dependence between two memory accesses.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DependenceKind {
+    /// Read After Write (flow dependence).
+    ReadAfterWrite,
+    /// Write After Read (anti dependence).
+    WriteAfterRead,
+    /// Write After Write (output dependence).
+    WriteAfterWrite,
+}
+
+impl DependenceKind {
+    fn reversed(self) -> Self {
+        match self {
+            DependenceKind::ReadAfterWrite => DependenceKind::WriteAfterRead,
+            DependenceKind::WriteAfterRead => DependenceKind::ReadAfterWrite,
+            DependenceKind::WriteAfterWrite => DependenceKind::WriteAfterWrite,
+        }
+    }
+}
+
+/// Dependence distance along one iteration variable.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distance {
+    /// The sink runs exactly this many iterations after the source.
+    Exact(isize),
+    /// The distance is unknown or not unique.
+    Any,
+}
+
+/// A [`Dependence`] between two memory accesses of the same [`Buffer`] with
+/// its distance vector over the iteration variables of a loop nest.
+#[derive(Clone, Debug)]
+pub struct Dependence {
+    kind: DependenceKind,
+    distance: Vec<Distance>,
+}
+
+impl Dependence {
+    /// Get the dependence kind.
+    pub fn get_kind(&self) -> DependenceKind {
+        self.kind
+    }
+
+    /// Get the distance vector, one entry per iteration variable from the
+    /// outermost to the innermost loop.
+    pub fn get_distance(&self) -> &Vec<Distance> {
+        &self.distance
+    }
+
+    /// The index of the loop carrying the dependence, i.e. the first entry
+    /// of the distance vector that is not exactly zero. `None` if the
+    /// dependence is loop independent.
+    pub fn get_carried_level(&self) -> Option<usize> {
+        self.distance
+            .iter()
+            .position(|distance| *distance != Distance::Exact(0))
+    }
+
+    /// Whether the dependence is preserved when the loops are reordered so
+    /// that `order[i]` becomes the `i`-th loop, i.e. whether the permuted
+    /// distance vector stays lexicographically positive.
+    ///
+    /// A [`Distance::Any`] entry may be negative, so it may never lead the
+    /// permuted vector. If it leads the original vector, the direction of
+    /// the dependence itself is unknown: every entry up to the first exact
+    /// one may decide it, so these loops must keep their relative order
+    /// ahead of the other loops carrying a distance.
+    pub fn is_preserved_by(&self, order: &[usize]) -> bool {
+        let levels = |order: &mut dyn Iterator<Item = usize>| {
+            order
+                .filter(|&level| self.distance[level] != Distance::Exact(0))
+                .collect::<Vec<_>>()
+        };
+        let original = levels(&mut (0..self.distance.len()));
+        let permuted = levels(&mut order.iter().copied());
+
+        match original.first().map(|&level| self.distance[level]) {
+            // Loop independent.
+            None => true,
+            Some(Distance::Exact(_)) => permuted.first().is_some_and(
+                |&level| matches!(self.distance[level], Distance::Exact(value) if value > 0),
+            ),
+            Some(Distance::Any) => {
+                let deciding = original
+                    .iter()
+                    .position(|&level| self.distance[level] != Distance::Any)
+                    .map_or(original.len(), |position| position + 1);
+                permuted.starts_with(&original[..deciding])
+            }
+        }
+    }
+}
+
+/// [`DependenceAnalysis`] computes RAW/WAR/WAW dependences between memory
+/// accesses over the iteration variables of a loop nest.
+///
+/// Following the polyhedral model, an access to a buffer at iteration `i`
+/// touches `A * i + o` where `A` and `o` come from an [`AccessMap`]. Two
+/// accesses with the same access matrix `A` (uniform dependence) touch the
+/// same element at iterations `i` and `i + d` when `A * d = o1 - o2`, which
+/// gives the distance vector `d`. Distances that are not uniquely determined,
+/// and all distances of non-uniform accesses, are reported as
+/// [`Distance::Any`].
+pub struct DependenceAnalysis {
+    ivars: Vec<Rc<IterationVar>>,
+    dependences: Vec<Dependence>,
+}
+
+impl DependenceAnalysis {
+    /// Create a new analysis over the given loop nest, from the outermost
+    /// to the innermost iteration variable.
+    pub fn new(ivars: Vec<Rc<IterationVar>>) -> Self {
+        DependenceAnalysis {
+            ivars,
+            dependences: vec![],
+        }
+    }
+
+    /// Compute the dependences between all loads, ops and stores of a block.
+    ///
+    /// Accesses are taken in program order: the read of the source and the
+    /// write of the destination of each input edge, then the accesses of
+    /// the ops in the block's subgraph through their [`AccessMap`], then
+    /// those of each output edge. Writes of buffers also read in the block
+    /// are paired with themselves as well, giving the output dependences
+    /// carried by the loops they do not index.
+    pub fn from_block(block: &ThrillerBlock) -> Self {
+        let mut analysis = Self::new(block.ivars.clone());
+
+        let subgraph = block.subgraph.borrow();
+        let nodes = subgraph
+            .topo_sort()
+            .unwrap_or_else(|_| subgraph.nodes.clone());
+        let op_accesses = nodes
+            .iter()
+            .flat_map(|node| match node.borrow().get_inner() {
+                ThrillerNodeInner::Op(task) => task.get_accesses(),
+                _ => vec![],
+            })
+            .collect::<Vec<_>>();
+
+        let edge_accesses = |edges: &Vec<Rc<AttachedEdge>>| {
+            edges
+                .iter()
+                .flat_map(|edge| [MemoryAccess::source_of(edge), MemoryAccess::target_of(edge)])
+                .collect::<Vec<_>>()
+        };
+        let accesses = edge_accesses(&block.inputs)
+            .into_iter()
+            .chain(op_accesses)
+            .chain(edge_accesses(&block.outputs))
+            .collect::<Vec<_>>();
+
+        let is_read = |buffer: &Rc<Buffer>| {
+            accesses.iter().any(|access| {
+                access.kind == AccessKind::Read && access.buffer.get_id() == buffer.get_id()
+            })
+        };
+
+        for (index, first) in accesses.iter().enumerate() {
+            // A write of a buffer also read in the loop, e.g. an accumulator
+            // updated by every iteration, depends on itself in the other
+            // iterations touching the same element.
+            let start = match first.kind {
+                AccessKind::Write if is_read(&first.buffer) => index,
+                _ => index + 1,
+            };
+            for second in accesses[start..].iter() {
+                if let Some(dependence) = analysis.analyze(first, second) {
+                    if dependence.get_carried_level().is_some() || !std::ptr::eq(first, second) {
+                        analysis.dependences.push(dependence);
+                    }
+                }
+            }
+        }
+
+        analysis
+    }
+
+    /// Get the dependences computed by [`Self::from_block`].
+    pub fn get_dependences(&self) -> &Vec<Dependence> {
+        &self.dependences
+    }
+
+    /// Compute the dependence between `first` and `second`, where `first`
+    /// comes before `second` in the loop body. Returns `None` if the two
+    /// accesses can never touch the same element.
+    pub fn analyze(&self, first: &MemoryAccess, second: &MemoryAccess) -> Option<Dependence> {
+        if first.buffer.get_id() != second.buffer.get_id() {
+            return None;
+        }
+
+        let mut kind = match (first.kind, second.kind) {
+            (AccessKind::Read, AccessKind::Read) => return None,
+            (AccessKind::Write, AccessKind::Read) => DependenceKind::ReadAfterWrite,
+            (AccessKind::Read, AccessKind::Write) => DependenceKind::WriteAfterRead,
+            (AccessKind::Write, AccessKind::Write) => DependenceKind::WriteAfterWrite,
+        };
+
+        let conservative = Dependence {
+            kind,
+            distance: vec![Distance::Any; self.ivars.len()],
+        };
+
+        let (Some(first_access), Some(second_access)) =
+            (first.aligned(&self.ivars), second.aligned(&self.ivars))
+        else {
+            return Some(conservative);
+        };
+
+        if first_access.matrix != second_access.matrix {
+            return Some(conservative);
+        }
+
+        // Rows depending on outer loops with different coefficients give
+        // no usable constraint.
+        let rows = (0..first_access.matrix.len())
+            .filter(|&row| first_access.outer[row] == second_access.outer[row])
+            .map(|row| {
+                (
+                    &first_access.matrix[row],
+                    first_access.offset[row] - second_access.offset[row],
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut distance: Vec<Option<isize>> = vec![None; self.ivars.len()];
+
+        // Solve `A * d = o1 - o2` by propagating rows with one unknown.
+        loop {
+            let mut progress = false;
+
+            for (row, rhs) in rows.iter() {
+                let mut residual = *rhs;
+                let mut unknowns = vec![];
+
+                for (column, &coef) in row.iter().enumerate() {
+                    if coef == 0 {
+                        continue;
+                    }
+                    match distance[column] {
+                        Some(value) => residual -= coef * value,
+                        None => unknowns.push(column),
+                    }
+                }
+
+                match unknowns.as_slice() {
+                    [] if residual != 0 => return None,
+                    [column] => {
+                        if residual % row[*column] != 0 {
+                            return None;
+                        }
+                        distance[*column] = Some(residual / row[*column]);
+                        progress = true;
+                    }
+                    _ => {}
+                }
+            }
+
+            if !progress {
+                break;
+            }
+        }
+
+        // A distance at least the trip count never happens.
+        for (ivar, value) in self.ivars.iter().zip(distance.iter()) {
+            if let (Some(value), (IterationBound::Fixed(lower), IterationBound::Fixed(upper))) =
+                (value, ivar.get_domain())
+            {
+                if value.unsigned_abs() >= upper.saturating_sub(*lower) {
+                    return None;
+                }
+            }
+        }
+
+        let mut distance = distance
+            .into_iter()
+            .map(|value| value.map_or(Distance::Any, Distance::Exact))
+            .collect::<Vec<_>>();
+
+        // A lexicographically negative distance means `second` actually
+        // runs first, so the dependence goes the other way.
+        if let Some(Distance::Exact(value)) = distance
+            .iter()
+            .find(|distance| **distance != Distance::Exact(0))
+        {
+            if *value < 0 {
+                kind = kind.reversed();
+                distance = distance
+                    .into_iter()
+                    .map(|distance| match distance {
+                        Distance::Exact(value) => Distance::Exact(-value),
+                        Distance::Any => Distance::Any,
+                    })
+                    .collect();
+            }
+        }
+
+        Some(Dependence { kind, distance })
+    }
+}
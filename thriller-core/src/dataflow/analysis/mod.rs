@@ -0,0 +1,9 @@
+mod dependence;
+mod reduction;
+mod register;
+
+pub use dependence::{
+    AccessKind, Dependence, DependenceAnalysis, DependenceKind, Distance, MemoryAccess,
+};
+pub use reduction::ReductionAnalysis;
+pub use register::RegisterPressure;
@@ -0,0 +1,90 @@
+use std::rc::Rc;
+
+use crate::{AttachedEdge, IterationVar, ThrillerBlock, Var};
+
+/// [`ReductionAnalysis`] determines where the stores of a [`ThrillerBlock`]
+/// have to be placed in its loop nest.
+///
+/// An iteration variable of the block is a reduction dimension of an output
+/// [`AttachedEdge`] if it does not appear in the destination access of the
+/// edge's [`crate::AccessMap`]: every iteration of that loop writes the same
+/// destination tile, so the result is only complete once the loop is done.
+/// The store is therefore placed at the innermost loop level outside all
+/// reduction loops.
+///
+/// Examples:
+/// In FlashAttention-v2 the output `O` is indexed by the outer loop `n`
+/// but not by the inner loop `k`, so it is written back once per `n`:
+/// ```cpp
+/// for (int n = 0; n < GIteratorV::sc0; ++n) {
+///     for (int k = 0; k < GIteratorQ::sc1; ++k) {
+///          ...
+///     }
+///     store_o(rO, gOs(n));
+/// }
+/// ```
+pub struct ReductionAnalysis {
+    reductions: Vec<Vec<Rc<IterationVar>>>,
+    depths: Vec<usize>,
+}
+
+impl ReductionAnalysis {
+    /// Analyze the output edges of the given block.
+    pub fn new(block: &ThrillerBlock) -> Self {
+        let mut reductions = vec![];
+        let mut depths = vec![];
+
+        for edge in block.outputs.iter() {
+            let edge_reductions = block
+                .ivars
+                .iter()
+                .filter(|ivar| !Self::is_in_target_access(edge, ivar))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            // The store sits right outside the outermost reduction loop.
+            let depth = block
+                .ivars
+                .iter()
+                .position(|ivar| {
+                    edge_reductions
+                        .iter()
+                        .any(|reduction| reduction.get_id() == ivar.get_id())
+                })
+                .unwrap_or(block.ivars.len());
+
+            reductions.push(edge_reductions);
+            depths.push(depth);
+        }
+
+        ReductionAnalysis { reductions, depths }
+    }
+
+    /// Whether `ivar` has a non-zero coefficient in the target access of `edge`.
+    fn is_in_target_access(edge: &AttachedEdge, ivar: &Rc<IterationVar>) -> bool {
+        let access = edge.get_access();
+        let column = access
+            .get_iter_vars()
+            .iter()
+            .position(|access_ivar| access_ivar.get_id() == ivar.get_id());
+
+        match (column, access.get_access_matrixs().get(1)) {
+            (Some(column), Some(matrix)) => matrix
+                .0
+                .iter()
+                .any(|row| row.get(column).is_some_and(|&coef| coef != 0)),
+            _ => false,
+        }
+    }
+
+    /// Get the reduction iteration variables of the output edge at `index`.
+    pub fn get_reduction_ivars(&self, index: usize) -> &Vec<Rc<IterationVar>> {
+        &self.reductions[index]
+    }
+
+    /// Get the loop depth of the store of the output edge at `index`,
+    /// where depth `0` is outside the loop nest.
+    pub fn get_store_depth(&self, index: usize) -> usize {
+        self.depths[index]
+    }
+}
@@ -0,0 +1,137 @@
+use std::rc::Rc;
+
+use crate::{
+    warn, AttachedEdge, Buffer, MemoryLevel, Task, ThrillerBlock, ThrillerError, ThrillerGraph,
+    ThrillerNodeInner, ThrillerResult,
+};
+
+/// Size in bytes of one register.
+const REGISTER_BYTES: usize = 4;
+
+/// [`RegisterPressure`] estimates the registers per thread taken by the
+/// register tiles and vectors live in each [`ThrillerBlock`].
+///
+/// The buffers live in a block are the register buffers accessed by the
+/// block and its nested blocks, plus those of the enclosing graphs and
+/// blocks, as they stay live across the loop nest. Each buffer is evenly
+/// distributed over the threads of the thread block. Only the tiles are
+/// counted, the registers used for indices and addresses come on top.
+///
+/// Blocks exceeding the per-thread register `limit` of the architecture,
+/// e.g. 255 on recent NVIDIA GPUs, will spill and are reported with a warning.
+pub struct RegisterPressure {
+    threads: usize,
+    limit: usize,
+    usages: Vec<(String, usize)>,
+}
+
+impl RegisterPressure {
+    /// Estimate the register usage of every block in `graph`, running on
+    /// thread blocks of `threads` threads.
+    pub fn new(graph: &ThrillerGraph, threads: usize, limit: usize) -> Self {
+        let mut pressure = RegisterPressure {
+            threads: threads.max(1),
+            limit,
+            usages: vec![],
+        };
+        pressure.visit(graph, &[]);
+
+        for (name, usage) in pressure.usages.iter() {
+            if *usage > limit {
+                warn!(
+                    "{} needs about {} registers per thread, more than the limit of {}.",
+                    name, usage, limit
+                );
+            }
+        }
+
+        pressure
+    }
+
+    /// Estimated registers per thread of each block, by block name.
+    pub fn get_usages(&self) -> &Vec<(String, usize)> {
+        &self.usages
+    }
+
+    /// The highest estimated registers per thread over all blocks.
+    pub fn max_usage(&self) -> usize {
+        self.usages
+            .iter()
+            .map(|(_, usage)| *usage)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The highest usage, or an error if it exceeds the limit.
+    pub fn check(&self) -> ThrillerResult<usize> {
+        let usage = self.max_usage();
+        if usage > self.limit {
+            return Err(ThrillerError::RegisterPressureExceeded(usage, self.limit));
+        }
+        Ok(usage)
+    }
+
+    /// Registers per thread taken by `buffers`.
+    fn registers(&self, buffers: &[Rc<Buffer>]) -> usize {
+        buffers
+            .iter()
+            .map(|buffer| {
+                let elements = buffer.get_shape().num_elements().div_ceil(self.threads);
+                (elements * buffer.get_dtype().size_in_bytes()).div_ceil(REGISTER_BYTES)
+            })
+            .sum()
+    }
+
+    fn visit(&mut self, graph: &ThrillerGraph, enclosing: &[Rc<Buffer>]) {
+        let mut scope = enclosing.to_vec();
+        for node in graph.nodes.iter() {
+            if let ThrillerNodeInner::Buffer(buffer) = node.borrow().get_inner() {
+                add_register_buffer(&mut scope, buffer);
+            }
+        }
+
+        for node in graph.nodes.iter() {
+            let block = match node.borrow().get_inner() {
+                ThrillerNodeInner::Block(block) => block.clone(),
+                _ => continue,
+            };
+
+            let mut live = scope.clone();
+            collect_block(&mut live, &block);
+            self.usages.push((block.get_name(), self.registers(&live)));
+
+            let mut body_scope = scope.clone();
+            add_edges(&mut body_scope, &block.inputs);
+            add_edges(&mut body_scope, &block.outputs);
+            self.visit(&block.subgraph.borrow(), &body_scope);
+        }
+    }
+}
+
+fn add_register_buffer(buffers: &mut Vec<Rc<Buffer>>, buffer: &Rc<Buffer>) {
+    if buffer.get_typing().get_memory_level() == MemoryLevel::Register
+        && !buffers.iter().any(|buf| buf.get_id() == buffer.get_id())
+    {
+        buffers.push(buffer.clone());
+    }
+}
+
+fn add_edges(buffers: &mut Vec<Rc<Buffer>>, edges: &[Rc<AttachedEdge>]) {
+    for edge in edges {
+        add_register_buffer(buffers, &edge.src);
+        add_register_buffer(buffers, &edge.dst);
+    }
+}
+
+/// Collect the register buffers accessed by `block` and its nested blocks.
+fn collect_block(buffers: &mut Vec<Rc<Buffer>>, block: &ThrillerBlock) {
+    add_edges(buffers, &block.inputs);
+    add_edges(buffers, &block.outputs);
+    for node in block.subgraph.borrow().nodes.iter() {
+        match node.borrow().get_inner() {
+            ThrillerNodeInner::Buffer(buffer) => add_register_buffer(buffers, buffer),
+            ThrillerNodeInner::Block(block) => collect_block(buffers, block),
+            ThrillerNodeInner::Op(_) => {}
+        }
+    }
+}
@@ -2,12 +2,33 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use std::vec::Vec;
 
-use crate::dataflow::{AttachedEdge, ThrillerGraph};
-use crate::error::ThrillerResult;
+use crate::dataflow::{AttachedEdge, ReductionAnalysis, ThrillerGraph};
+use crate::error::{ThrillerError, ThrillerResult};
 use crate::kernels::sync::Sync;
 use crate::task::Task;
 use crate::var::Var;
-use crate::{next_id, BufType, IterationBound, IterationVar};
+use crate::{next_id, BufType, Buffer, IterationBound, IterationVar, ThrillerNodeInner};
+
+/// The threads of the thread block sharing the tiles of a [`ThrillerBlock`],
+/// which its barriers synchronize.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ThreadScope {
+    /// All threads of the thread block, synchronized by `__syncthreads()`.
+    #[default]
+    Block,
+    /// A group of warps, e.g. the producer or consumer warps of a warp
+    /// specialized kernel, synchronized by the named barrier `id`.
+    Warps {
+        /// The barrier id in `1..16`, 0 being used by `__syncthreads()`.
+        id: usize,
+        /// The number of threads in the group, a multiple of the warp size.
+        threads: usize,
+    },
+    /// A single warp working on its own tiles, e.g. a warp tile of a GEMM
+    /// staged through shared memory into its register tiles, synchronized
+    /// by `__syncwarp()`.
+    Warp,
+}
 
 /// [`ThrillerBlock`] represents the data-parallel repetition of a
 /// dataflow task int form of a d-dimensional dataflow node.
@@ -15,12 +36,20 @@ use crate::{next_id, BufType, IterationBound, IterationVar};
 /// A [`ThrillerBlock`] contains a d-dimensional nested loop with the input [`AttachedEdge`]
 /// and output [`AttachedEdge`] representing tiling load and store operations,
 /// while the subgraph represents the computation operations within the nested loops.
+#[derive(Clone)]
 pub struct ThrillerBlock {
     id: usize,
     pub(crate) inputs: Vec<Rc<AttachedEdge>>,
     pub(crate) outputs: Vec<Rc<AttachedEdge>>,
     pub(crate) subgraph: Rc<RefCell<ThrillerGraph>>,
     pub(crate) ivars: Vec<Rc<IterationVar>>,
+    stages: usize,
+    /// Whether the barrier after the loop nest is kept.
+    pub(crate) loop_barrier: bool,
+    /// Whether the barrier protecting the shared tiles reloaded by the next
+    /// innermost iteration is kept.
+    pub(crate) stage_barrier: bool,
+    scope: ThreadScope,
 }
 
 impl ThrillerBlock {
@@ -37,92 +66,291 @@ impl ThrillerBlock {
             subgraph,
             ivars,
             id: next_id(),
+            stages: 1,
+            loop_barrier: true,
+            stage_barrier: true,
+            scope: ThreadScope::Block,
         }
     }
 
-    fn emit_loop(&self) -> ThrillerResult<String> {
-        let mut code = String::new();
+    /// Set the number of pipeline stages of the shared tiles loaded from
+    /// global memory in the innermost loop.
+    ///
+    /// With `stages > 1`, each of these tiles is allocated `stages` times
+    /// and the load of iteration `i + stages - 1` is issued as an async copy
+    /// before computing iteration `i`.
+    pub fn set_stages(&mut self, stages: usize) {
+        self.stages = stages;
+    }
+
+    /// Get the number of pipeline stages.
+    pub fn get_stages(&self) -> usize {
+        self.stages
+    }
+
+    /// Set the threads sharing the tiles of the block, [`ThreadScope::Block`]
+    /// by default. The shared tiles accessed by the block must not be
+    /// accessed by other threads, neither in the block nor in the tasks
+    /// following it.
+    pub fn set_thread_scope(&mut self, scope: ThreadScope) {
+        self.scope = scope;
+    }
+
+    /// Get the threads sharing the tiles of the block.
+    pub fn get_thread_scope(&self) -> ThreadScope {
+        self.scope
+    }
 
-        let mut indent = 0;
+    /// The barrier synchronizing the threads of the block's scope.
+    fn barrier(&self) -> Sync {
+        match self.scope {
+            ThreadScope::Block => Sync::Threads,
+            ThreadScope::Warps { id, threads } => Sync::NamedBarrier { id, threads },
+            ThreadScope::Warp => Sync::Warp,
+        }
+    }
 
-        // Generate loop.
+    /// Whether the load along `edge` is software pipelined.
+    fn is_pipelined(&self, edge: &AttachedEdge) -> bool {
+        self.stages > 1
+            && !self.ivars.is_empty()
+            && *edge.src.get_typing() == BufType::GlobalTile
+            && *edge.dst.get_typing() == BufType::SharedTile
+    }
+
+    /// Shared tiles loaded in a software pipeline, allocated once per stage.
+    pub(crate) fn get_pipelined_buffers(&self) -> Vec<Rc<Buffer>> {
+        self.inputs
+            .iter()
+            .filter(|edge| self.is_pipelined(edge))
+            .map(|edge| edge.dst.clone())
+            .collect()
+    }
+
+    /// Collect the free variables of the symbolic loop bounds in this block
+    /// and its nested blocks, deduplicated by name, e.g. `K` and `TILE_K`
+    /// for `k < K / TILE_K`.
+    pub(crate) fn collect_symbolic_vars(&self, vars: &mut Vec<String>) -> ThrillerResult<()> {
         for ivar in self.ivars.iter() {
             let (lower, upper) = ivar.get_domain();
-
-            code += match (lower, upper) {
-                (IterationBound::Fixed(lower), IterationBound::Fixed(upper)) => {
-                    format!(
-                        "{indent}for(int {ivar} = {lower}; {ivar} < {upper}; ++{ivar}){{\n",
-                        indent = " ".repeat(indent),
-                        ivar = ivar.get_name(),
-                        lower = lower,
-                        upper = upper
-                    )
+            for bound in [lower, upper] {
+                if let IterationBound::Var(var) = bound {
+                    for name in free_vars(var.get_name())? {
+                        if !vars.contains(&name) {
+                            vars.push(name);
+                        }
+                    }
                 }
+            }
+        }
 
-                _ => todo!(),
+        for node in self.subgraph.borrow().nodes.iter() {
+            if let ThrillerNodeInner::Block(block) = node.borrow().get_inner() {
+                block.collect_symbolic_vars(vars)?;
             }
-            .as_str();
+        }
+
+        Ok(())
+    }
+
+    /// Open the loop at the given depth (1 being the outermost loop).
+    fn emit_loop(&self, depth: usize) -> ThrillerResult<String> {
+        let ivar = &self.ivars[depth - 1];
+        let (lower, upper) = ivar.get_domain();
+
+        // Symbolic bounds are emitted by name and bound to kernel parameters
+        // in the function signature.
+        Ok(format!(
+            "{indent}for(int {ivar} = {lower}; {ivar} < {upper}; ++{ivar}){{\n",
+            indent = " ".repeat((depth - 1) * 4),
+            ivar = ivar.get_name(),
+            lower = lower,
+            upper = upper
+        ))
+    }
+
+    /// Close the loop at the given depth (1 being the outermost loop).
+    fn emit_loop_closure(&self, depth: usize) -> ThrillerResult<String> {
+        Ok(format!(
+            "{indent}}}\n",
+            indent = " ".repeat((depth - 1) * 4)
+        ))
+    }
+
+    /// The pipeline stage of the innermost iteration `ahead` iterations
+    /// after the current one.
+    fn emit_stage(&self, ahead: usize) -> String {
+        let ivar = self.ivars.last().unwrap();
+        let mut iteration = match ivar.get_domain() {
+            (IterationBound::Fixed(0), _) => ivar.get_name().clone(),
+            (lower, _) => format!("{} - {}", ivar.get_name(), lower),
+        };
+        if ahead > 0 {
+            iteration = format!("{} + {}", iteration, ahead);
+        }
+
+        if iteration == *ivar.get_name() {
+            format!("{} % {}", iteration, self.stages)
+        } else {
+            format!("({}) % {}", iteration, self.stages)
+        }
+    }
 
-            indent += 4;
+    /// The buffer variable, indexed by the current stage if pipelined.
+    fn emit_buf_var(&self, buf: &Buffer) -> String {
+        if self
+            .get_pipelined_buffers()
+            .iter()
+            .any(|b| b.get_id() == buf.get_id())
+        {
+            format!("{}[{}]", buf.get_name(), self.emit_stage(0))
+        } else {
+            buf.get_name().clone()
+        }
+    }
+
+    /// Issue the pipelined loads with the innermost iteration variable
+    /// replaced by `iteration`, into the shared tiles of `stage`.
+    fn emit_async_load(
+        &self,
+        iteration: &str,
+        stage: &str,
+        indent: &str,
+    ) -> ThrillerResult<String> {
+        let mut code = String::new();
+        let innermost = self.ivars.last().unwrap().get_id();
+        let ivar_code = |ivar: &IterationVar| {
+            if ivar.get_id() == innermost {
+                iteration.to_string()
+            } else {
+                ivar.get_name().clone()
+            }
+        };
+
+        for edge in self.inputs.iter().filter(|edge| self.is_pipelined(edge)) {
+            code += format!(
+                "{indent}loader_tile_g2s_{sid}_to_{did}({sbuf_var}({src_access}), {dbuf_var}[{stage}]({target_access}));\n",
+                indent = indent,
+                sid = edge.src.get_id(),
+                did = edge.dst.get_id(),
+                sbuf_var = edge.src.get_name(),
+                src_access = edge.access.emit_access_with(0, &ivar_code)?.join(", "),
+                dbuf_var = edge.dst.get_name(),
+                stage = stage,
+                target_access = edge.access.emit_access_with(1, &ivar_code)?.join(", ")
+            )
+            .as_str();
         }
 
         Ok(code)
     }
 
-    fn emit_loop_closure(&self) -> ThrillerResult<String> {
-        let mut indent = ((self.ivars.len() - 1) * 4) as isize;
+    /// Fill the first `stages - 1` stages before entering the innermost loop.
+    ///
+    /// One group is committed per stage even if the loop runs fewer
+    /// iterations, as the wait in [`Self::emit_prefetch`] counts on
+    /// `stages - 1` groups committed ahead.
+    fn emit_prologue(&self) -> ThrillerResult<String> {
         let mut code = String::new();
+        let indent = " ".repeat((self.ivars.len() - 1) * 4);
+        let (lower, upper) = self.ivars.last().unwrap().get_domain();
 
-        while indent >= 0 {
-            code += format!("{indent}}}\n", indent = " ".repeat(indent as usize)).as_str();
-            indent -= 4;
+        for stage in 0..self.stages - 1 {
+            match (lower, upper) {
+                (IterationBound::Fixed(lower), IterationBound::Fixed(upper)) => {
+                    // Out of the iteration domain, the group stays empty.
+                    if lower + stage < *upper {
+                        code += self
+                            .emit_async_load(
+                                &(lower + stage).to_string(),
+                                &stage.to_string(),
+                                &indent,
+                            )?
+                            .as_str();
+                    }
+                }
+                (lower, _) => {
+                    let iteration = format!("({} + {})", lower, stage);
+                    code += format!("{indent}if({iteration} < {upper}){{\n").as_str();
+                    code += self
+                        .emit_async_load(&iteration, &stage.to_string(), &format!("{indent}    "))?
+                        .as_str();
+                    code += format!("{indent}}}\n").as_str();
+                }
+            }
+            code += format!("{indent}{}", Sync::CommitGroup.emit()).as_str();
         }
 
         Ok(code)
     }
 
+    /// Issue the loads `stages - 1` iterations ahead and wait for the
+    /// current stage.
+    fn emit_prefetch(&self) -> ThrillerResult<String> {
+        let mut code = String::new();
+        let indent = " ".repeat(self.ivars.len() * 4);
+        let ivar = self.ivars.last().unwrap();
+        let (_, upper) = ivar.get_domain();
+        let ahead = self.stages - 1;
+
+        code += format!(
+            "{indent}if({ivar} + {ahead} < {upper}){{\n",
+            ivar = ivar.get_name()
+        )
+        .as_str();
+        code += self
+            .emit_async_load(
+                &format!("({} + {})", ivar.get_name(), ahead),
+                &self.emit_stage(ahead),
+                &format!("{indent}    "),
+            )?
+            .as_str();
+        code += format!("{indent}}}\n").as_str();
+
+        code += format!("{indent}{}", Sync::CommitGroup.emit()).as_str();
+        code += format!("{indent}{}", Sync::WaitGroup(ahead).emit()).as_str();
+        code += format!("{indent}{}", self.barrier().emit()).as_str();
+
+        Ok(code)
+    }
+
+    /// Wait for the issued async copies and make the shared tiles visible
+    /// to the threads of the block's scope.
+    fn emit_async_copy_sync(&self, indent: &str) -> String {
+        [Sync::CommitGroup, Sync::WaitGroup(0), self.barrier()]
+            .iter()
+            .map(|sync| format!("{indent}{}", sync.emit()))
+            .collect()
+    }
+
+    /// Global to shared loads are async copies waited for before the shared
+    /// tiles are read, while loads into registers are private to each thread
+    /// and need no barrier.
     fn emit_load(&self) -> ThrillerResult<String> {
         let mut code = String::new();
         let indent = " ".repeat(self.ivars.len() * 4);
 
-        let mut insert_copy_async = false;
-        let mut insert_syncthreads = false;
+        // Shared tiles written by async copies that are not yet visible to
+        // the whole thread block.
+        let mut pending: Vec<usize> = vec![];
 
         for edge in self.inputs.iter() {
-            // Insert `syncthreads()` when loading tiles.
-            insert_syncthreads = true;
+            // Pipelined loads are issued ahead of time.
+            if self.is_pipelined(edge) {
+                continue;
+            }
 
             let sbuf = &edge.src;
             let dbuf = &edge.dst;
 
-            let sbuf_var = sbuf.get_name();
-            let dbuf_var = dbuf.get_name();
+            let sbuf_var = self.emit_buf_var(sbuf);
+            let dbuf_var = self.emit_buf_var(dbuf);
 
             let sbuf_id = sbuf.get_id();
             let dbuf_id = dbuf.get_id();
 
-            let source_access_code = edge.emit_source_access()?.iter().enumerate().fold(
-                String::new(),
-                |acc, (index, access)| {
-                    if index == 0 {
-                        access.to_string()
-                    } else {
-                        format!("{acc}, {access}", acc = acc, access = access)
-                    }
-                },
-            );
-
-            let target_access_code = edge.emit_target_access()?.iter().enumerate().fold(
-                String::new(),
-                |acc, (index, access)| {
-                    if index == 0 {
-                        access.to_string()
-                    } else {
-                        format!("{acc}, {access}", acc = acc, access = access)
-                    }
-                },
-            );
+            let source_access_code = edge.emit_source_access()?.join(", ");
+            let target_access_code = edge.emit_target_access()?.join(", ");
 
             match (sbuf.get_typing(), dbuf.get_typing()) {
                 (BufType::GlobalTile, BufType::RegTile) => {
@@ -140,7 +368,11 @@ impl ThrillerBlock {
                 }
 
                 (BufType::SharedTile, BufType::RegTile) => {
-                    insert_copy_async = true;
+                    if pending.contains(&sbuf_id) {
+                        code += self.emit_async_copy_sync(&indent).as_str();
+                        pending.clear();
+                    }
+
                     code += format!(
                         "{indent}loader_tile_s2r_{sid}_to_{did}({sbuf_var}({src_access}), {dbuf_var}({target_access}));\n",
                         indent = indent,
@@ -155,6 +387,7 @@ impl ThrillerBlock {
                 }
 
                 (BufType::GlobalTile, BufType::SharedTile) => {
+                    pending.push(dbuf_id);
                     code += format!(
                         "{indent}loader_tile_g2s_{sid}_to_{did}({sbuf_var}({src_access}), {dbuf_var}({target_access}));\n",
                         indent = indent,
@@ -172,88 +405,115 @@ impl ThrillerBlock {
             }
         }
 
-        if insert_copy_async {
-            code += format!(
-                "{indent}{copy_async}",
-                indent = indent,
-                copy_async = Sync::emit_copy_async()
-            )
-            .as_str();
-        }
-
-        if insert_syncthreads {
-            code += format!(
-                "{indent}{syncthreads}",
-                indent = indent,
-                syncthreads = Sync::emit_sync()
-            )
-            .as_str();
+        if !pending.is_empty() {
+            code += self.emit_async_copy_sync(&indent).as_str();
         }
 
         Ok(code)
     }
 
-    fn emit_store(&self) -> ThrillerResult<String> {
+    fn emit_store(&self, edge: &AttachedEdge, indent: &str) -> ThrillerResult<String> {
         let mut code = String::new();
 
-        for edge in self.outputs.iter() {
-            let sbuf = &edge.src;
-            let dbuf = &edge.dst;
-
-            // TODO(KuangjuX): Support Access Memory code generation.
-
-            let sbuf_var = sbuf.get_name();
-            let dbuf_var = dbuf.get_name();
-
-            let sbuf_id = sbuf.get_id();
-            let dbuf_id = dbuf.get_id();
-
-            match (sbuf.get_typing(), dbuf.get_typing()) {
-                (BufType::RegTile, BufType::GlobalTile) => {
-                    code += format!(
-                        "storer_tile_r2g_{sid}_to_{did}({sbuf_var}, {dbuf_var});\n",
-                        sid = sbuf_id,
-                        did = dbuf_id,
-                        sbuf_var = sbuf_var,
-                        dbuf_var = dbuf_var
-                    )
-                    .as_str();
-                }
-
-                (BufType::RegTile, BufType::SharedTile) => {
-                    code += format!(
-                        "storer_tile_r2s_{sid}_to_{did}({sbuf_var}, {dbuf_var});\n",
-                        sid = sbuf_id,
-                        did = dbuf_id,
-                        sbuf_var = sbuf_var,
-                        dbuf_var = dbuf_var
-                    )
-                    .as_str();
-                }
+        let sbuf = &edge.src;
+        let dbuf = &edge.dst;
+
+        let sbuf_var = sbuf.get_name();
+        let dbuf_var = dbuf.get_name();
+
+        let sbuf_id = sbuf.get_id();
+        let dbuf_id = dbuf.get_id();
+
+        let source_access_code = edge.emit_source_access()?.join(", ");
+        let target_access_code = edge.emit_target_access()?.join(", ");
+
+        match (sbuf.get_typing(), dbuf.get_typing()) {
+            (BufType::RegTile, BufType::GlobalTile) => {
+                code += format!(
+                    "{indent}storer_tile_r2g_{sid}_to_{did}({sbuf_var}({src_access}), {dbuf_var}({target_access}));\n",
+                    indent = indent,
+                    sid = sbuf_id,
+                    did = dbuf_id,
+                    sbuf_var = sbuf_var,
+                    src_access = source_access_code,
+                    dbuf_var = dbuf_var,
+                    target_access = target_access_code
+                )
+                .as_str();
+            }
 
-                (BufType::SharedTile, BufType::GlobalTile) => {
-                    code += format!(
-                        "storer_tile_s2g_{sid}_to_{did}({sbuf_var}, {dbuf_var});\n",
-                        sid = sbuf_id,
-                        did = dbuf_id,
-                        sbuf_var = sbuf_var,
-                        dbuf_var = dbuf_var
-                    )
-                    .as_str();
-                }
+            (BufType::RegTile, BufType::SharedTile) => {
+                code += format!(
+                    "{indent}storer_tile_r2s_{sid}_to_{did}({sbuf_var}({src_access}), {dbuf_var}({target_access}));\n",
+                    indent = indent,
+                    sid = sbuf_id,
+                    did = dbuf_id,
+                    sbuf_var = sbuf_var,
+                    src_access = source_access_code,
+                    dbuf_var = dbuf_var,
+                    target_access = target_access_code
+                )
+                .as_str();
+            }
 
-                _ => todo!(),
+            (BufType::SharedTile, BufType::GlobalTile) => {
+                code += format!(
+                    "{indent}storer_tile_s2g_{sid}_to_{did}({sbuf_var}({src_access}), {dbuf_var}({target_access}));\n",
+                    indent = indent,
+                    sid = sbuf_id,
+                    did = dbuf_id,
+                    sbuf_var = sbuf_var,
+                    src_access = source_access_code,
+                    dbuf_var = dbuf_var,
+                    target_access = target_access_code
+                )
+                .as_str();
             }
+
+            _ => todo!(),
         }
 
         Ok(code)
     }
 
+    /// Whether a barrier is emitted after the loop nest.
+    ///
+    /// Register tiles are private to each thread, while shared tiles and
+    /// global writes of the loop nest have to be visible to the threads of
+    /// the block's [`ThreadScope`] before the following tasks access them.
+    pub(crate) fn has_loop_barrier(&self) -> bool {
+        let is_shared = |edge: &Rc<AttachedEdge>| {
+            *edge.src.get_typing() == BufType::SharedTile
+                || *edge.dst.get_typing() == BufType::SharedTile
+        };
+        let touches_shared = self.inputs.iter().chain(self.outputs.iter()).any(is_shared);
+        let writes_global = self
+            .outputs
+            .iter()
+            .any(|edge| *edge.dst.get_typing() == BufType::GlobalTile);
+
+        self.loop_barrier && (touches_shared || writes_global)
+    }
+
+    /// Whether a barrier is emitted at the end of each innermost iteration
+    /// before the next iteration loads global tiles into shared tiles, be it
+    /// into a pipeline stage by the next prefetch or into the very shared
+    /// tiles other threads may still be reading.
+    pub(crate) fn has_stage_barrier(&self) -> bool {
+        self.stage_barrier
+            && !self.ivars.is_empty()
+            && self.inputs.iter().any(|edge| {
+                *edge.src.get_typing() == BufType::GlobalTile
+                    && *edge.dst.get_typing() == BufType::SharedTile
+            })
+    }
+
     fn emit_sync(&self) -> ThrillerResult<String> {
         let mut code = String::new();
 
-        // TODO(KuangjuX): Check Memory Hiercary and insert sync primitive.
-        code += Sync::emit_sync().as_str();
+        if self.has_loop_barrier() {
+            code += self.barrier().emit().as_str();
+        }
 
         Ok(code)
     }
@@ -262,9 +522,20 @@ impl ThrillerBlock {
     pub(crate) fn emit_block(&self) -> ThrillerResult<String> {
         let mut code = String::new();
 
-        code += self.emit_loop()?.as_str();
+        let pipelined = !self.get_pipelined_buffers().is_empty();
+
+        for depth in 1..=self.ivars.len() {
+            if pipelined && depth == self.ivars.len() {
+                code += self.emit_prologue()?.as_str();
+            }
+            code += self.emit_loop(depth)?.as_str();
+        }
         let indent = " ".repeat(self.ivars.len() * 4);
 
+        if pipelined {
+            code += self.emit_prefetch()?.as_str();
+        }
+
         code += self.emit_load()?.as_str();
 
         let subgraph_code = self.subgraph.borrow().emit()?;
@@ -273,14 +544,43 @@ impl ThrillerBlock {
             code += format!("{indent}{line}\n", indent = indent, line = line).as_str();
         }
 
-        code += self.emit_loop_closure()?.as_str();
+        // The shared tiles read in this iteration are overwritten by the
+        // loads of the next one.
+        if self.has_stage_barrier() {
+            code += format!("{indent}{}", self.barrier().emit()).as_str();
+        }
+
+        // Stores are placed right outside their reduction loops, before
+        // the enclosing loop is closed.
+        let reduction = ReductionAnalysis::new(self);
+
+        for depth in (1..=self.ivars.len()).rev() {
+            let indent = " ".repeat(depth * 4);
+            for (index, edge) in self.outputs.iter().enumerate() {
+                if reduction.get_store_depth(index) == depth {
+                    code += self.emit_store(edge, &indent)?.as_str();
+                }
+            }
+
+            code += self.emit_loop_closure(depth)?.as_str();
+
+            if pipelined && depth == self.ivars.len() {
+                code += format!(
+                    "{indent}{wait}",
+                    indent = " ".repeat((depth - 1) * 4),
+                    wait = Sync::WaitGroup(0).emit()
+                )
+                .as_str();
+            }
+        }
 
         code += self.emit_sync()?.as_str();
 
-        // TODO(KuangjuX): Determine whether the `store` operation is
-        // codegen inside or outside the nested loop based on the
-        // [`AccessMap`].
-        code += self.emit_store()?.as_str();
+        for (index, edge) in self.outputs.iter().enumerate() {
+            if reduction.get_store_depth(index) == 0 {
+                code += self.emit_store(edge, "")?.as_str();
+            }
+        }
 
         Ok(code)
     }
@@ -295,3 +595,30 @@ impl Task for ThrillerBlock {
         format!("block_{}", self.id)
     }
 }
+
+/// The identifiers of a symbolic bound, which may only combine identifiers
+/// and integers with arithmetic operators and parentheses.
+fn free_vars(expr: &str) -> ThrillerResult<Vec<String>> {
+    let mut names: Vec<String> = vec![];
+
+    let tokens = expr
+        .split(|c: char| c.is_whitespace() || "+-*/%()".contains(c))
+        .filter(|token| !token.is_empty());
+    for token in tokens {
+        if token.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+
+        let is_identifier = !token.starts_with(|c: char| c.is_ascii_digit())
+            && token.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !is_identifier {
+            return Err(ThrillerError::InvalidLoopBound(expr.to_string()));
+        }
+
+        if !names.iter().any(|name| name == token) {
+            names.push(token.to_string());
+        }
+    }
+
+    Ok(names)
+}
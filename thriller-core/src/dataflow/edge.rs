@@ -62,6 +62,16 @@ impl AttachedEdge {
         &self.access
     }
 
+    /// A copy of the edge with its access pattern replaced.
+    pub(crate) fn with_access(&self, access: Rc<AccessMap>) -> Self {
+        AttachedEdge {
+            id: self.id,
+            src: self.src.clone(),
+            dst: self.dst.clone(),
+            access,
+        }
+    }
+
     /// Emit source Memory Access code.
     pub fn emit_source_access(&self) -> ThrillerResult<Vec<String>> {
         self.access.emit_access(0)
@@ -1,12 +1,15 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::rc::Rc;
 use std::vec::Vec;
 
 use crate::dataflow::{ThrillerEdge, ThrillerNode, ThrillerNodeInner};
-use crate::debug;
 use crate::task::Task;
-use crate::{next_id, ThrillerResult};
+use crate::{debug, error};
+use crate::{next_id, ThrillerError, ThrillerResult};
+
+/// Orders independent nodes in [`ThrillerGraph::topo_sort`], lowest first.
+type Priority = Box<dyn Fn(&ThrillerNode) -> isize>;
 
 /// [`ThrillerGraph`] repersents a dataflow task graph within
 /// a d-dimension loop nest.
@@ -16,6 +19,7 @@ pub struct ThrillerGraph {
     id: usize,
     pub(crate) nodes: Vec<Rc<RefCell<ThrillerNode>>>,
     pub(crate) edges: Vec<Rc<ThrillerEdge>>,
+    priority: Option<Priority>,
 }
 
 impl ThrillerGraph {
@@ -25,9 +29,20 @@ impl ThrillerGraph {
             id: next_id(),
             nodes: Vec::new(),
             edges: Vec::new(),
+            priority: None,
         }
     }
 
+    /// Set the priority used to order independent nodes in [`Self::topo_sort`].
+    ///
+    /// Among the nodes whose dependencies are satisfied, the one with the
+    /// lowest priority is emitted first, e.g. returning a lower value for
+    /// load blocks and a higher one for store blocks emits loads first and
+    /// stores last. Ties are broken by the order in which nodes were added.
+    pub fn set_priority(&mut self, priority: impl Fn(&ThrillerNode) -> isize + 'static) {
+        self.priority = Some(Box::new(priority));
+    }
+
     /// Add nodes into the graph.
     pub fn add_nodes(&mut self, nodes: Vec<Rc<RefCell<ThrillerNode>>>) {
         self.nodes.extend(nodes);
@@ -58,14 +73,29 @@ impl ThrillerGraph {
     }
 
     /// Topological sort the nodes in the graph.
-    pub fn topo_sort(&self) -> Vec<Rc<RefCell<ThrillerNode>>> {
+    ///
+    /// The order is deterministic: independent nodes are ordered by the
+    /// priority given in [`Self::set_priority`] and then by the order in
+    /// which they were added with [`Self::add_nodes`].
+    ///
+    /// Returns [`ThrillerError::GraphCycle`] with the names of the nodes on
+    /// the offending cycle if the graph is not acyclic.
+    pub fn topo_sort(&self) -> ThrillerResult<Vec<Rc<RefCell<ThrillerNode>>>> {
         let mut sorted_nodes = Vec::new();
-        // (id, (in_degrees, node))
-        let mut in_degrees: HashMap<usize, (usize, &Rc<RefCell<ThrillerNode>>)> = HashMap::new();
+        // id -> insertion index
+        let mut indices: HashMap<usize, usize> = HashMap::new();
+        let mut in_degrees = Vec::with_capacity(self.nodes.len());
+        let mut priorities = Vec::with_capacity(self.nodes.len());
 
-        for node in &self.nodes {
+        for (index, node) in self.nodes.iter().enumerate() {
             let ref_node = node.borrow();
-            in_degrees.insert(ref_node.get_id(), (ref_node.get_in_degrees(), node));
+            indices.insert(ref_node.get_id(), index);
+            in_degrees.push(ref_node.get_in_degrees());
+            priorities.push(
+                self.priority
+                    .as_ref()
+                    .map_or(0, |priority| priority(&ref_node)),
+            );
             debug!(
                 "{} have {} in_degrees.",
                 ref_node.get_id(),
@@ -73,32 +103,89 @@ impl ThrillerGraph {
             );
         }
 
-        while !in_degrees.is_empty() {
-            let node_ids = in_degrees.keys().cloned().collect::<Vec<_>>();
-            for node_id in node_ids {
-                let (in_degree, node) = in_degrees[&node_id];
-                if in_degree == 0 {
-                    sorted_nodes.push(node.clone());
+        // (priority, insertion index)
+        let mut ready: BTreeSet<(isize, usize)> = in_degrees
+            .iter()
+            .enumerate()
+            .filter(|(_, &in_degree)| in_degree == 0)
+            .map(|(index, _)| (priorities[index], index))
+            .collect();
+        let mut visited = vec![false; self.nodes.len()];
+
+        while let Some((priority, index)) = ready.iter().next().cloned() {
+            ready.remove(&(priority, index));
+            visited[index] = true;
+
+            let node = &self.nodes[index];
+            sorted_nodes.push(node.clone());
+
+            for next in node.borrow().get_nexts() {
+                let next_index = indices[&next.borrow().get_id()];
+                in_degrees[next_index] -= 1;
+                if in_degrees[next_index] == 0 {
+                    ready.insert((priorities[next_index], next_index));
+                }
+            }
+        }
 
-                    for next in node.borrow_mut().get_nexts() {
-                        let next_id = next.borrow().get_id();
-                        let (in_degree, _) = in_degrees.get_mut(&next_id).unwrap();
-                        *in_degree -= 1;
-                    }
+        // Every remaining node still has a remaining predecessor.
+        if sorted_nodes.len() != self.nodes.len() {
+            let remaining: HashMap<usize, usize> = indices
+                .into_iter()
+                .filter(|(_, index)| !visited[*index])
+                .collect();
+            let cycle = self.find_cycle(&remaining);
+            error!("Cycle detected in graph: {}", cycle.join(" -> "));
+            return Err(ThrillerError::GraphCycle(cycle));
+        }
+
+        Ok(sorted_nodes)
+    }
 
-                    in_degrees.remove(&node_id);
+    /// Walk backwards through the remaining nodes until a node repeats,
+    /// and return the names of the nodes on that cycle in edge order.
+    fn find_cycle(&self, remaining: &HashMap<usize, usize>) -> Vec<String> {
+        let mut path: Vec<Rc<RefCell<ThrillerNode>>> = vec![];
+        let first = remaining.values().min().unwrap();
+        let mut current = self.nodes[*first].clone();
+
+        loop {
+            let id = current.borrow().get_id();
+            if let Some(start) = path.iter().position(|node| node.borrow().get_id() == id) {
+                return path[start..]
+                    .iter()
+                    .rev()
+                    .map(|node| node.borrow().get_node_name())
+                    .collect();
+            }
+
+            let prev = current
+                .borrow()
+                .get_prevs()
+                .iter()
+                .find(|prev| remaining.contains_key(&prev.borrow().get_id()))
+                .cloned();
+            path.push(current);
+
+            match prev {
+                Some(prev) => current = prev,
+                // The blocking predecessor is not part of this graph.
+                None => {
+                    return path
+                        .iter()
+                        .rev()
+                        .map(|node| node.borrow().get_node_name())
+                        .collect()
                 }
             }
         }
-
-        sorted_nodes
     }
 }
 
 impl Task for ThrillerGraph {
     fn emit(&self) -> ThrillerResult<String> {
         let mut code = String::new();
-        let sorted_nodes = self.topo_sort();
+        let sorted_nodes = self.topo_sort()?;
 
         for node in sorted_nodes {
             match node.borrow().get_inner() {
@@ -1,11 +1,19 @@
+mod analysis;
 mod block;
 mod edge;
 mod graph;
 mod node;
 mod pass;
 
-pub use block::ThrillerBlock;
+pub use analysis::{
+    AccessKind, Dependence, DependenceAnalysis, DependenceKind, Distance, MemoryAccess,
+    ReductionAnalysis, RegisterPressure,
+};
+pub use block::{ThreadScope, ThrillerBlock};
 pub use edge::{AttachedEdge, ThrillerEdge};
 pub use graph::ThrillerGraph;
 pub use node::{ThrillerNode, ThrillerNodeInner};
-pub use pass::{AllocateEdge, AllocateVar, GraphPass};
+pub use pass::{
+    AllocateEdge, AllocateVar, BarrierElimination, Diagnostic, GraphPass, LoopFusion,
+    LoopInterchange, LoopTiling, ShapeValidation, SharedMemoryPlanner,
+};
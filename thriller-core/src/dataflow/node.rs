@@ -23,6 +23,13 @@ pub enum ThrillerNodeInner {
     Block(Rc<ThrillerBlock>),
 }
 
+impl ThrillerNodeInner {
+    /// Create an operation from the given task.
+    pub fn op<T: Task + 'static>(task: T) -> Self {
+        ThrillerNodeInner::Op(Box::new(task))
+    }
+}
+
 /// [`ThrillerNode`] represents an abstract node element that can represent a Buffer Node,
 /// Operator Node and Block Node.
 pub struct ThrillerNode {
@@ -58,7 +65,8 @@ impl ThrillerNode {
         }
     }
 
-    pub(crate) fn get_id(&self) -> usize {
+    /// Get the unique id of the node.
+    pub fn get_id(&self) -> usize {
         self.id
     }
 
@@ -66,7 +74,6 @@ impl ThrillerNode {
         self.in_degrees
     }
 
-    #[allow(dead_code)]
     pub(crate) fn get_prevs(&self) -> &Vec<Rc<RefCell<ThrillerNode>>> {
         &self.prevs
     }
@@ -80,6 +87,14 @@ impl ThrillerNode {
         &self.inner
     }
 
+    pub(crate) fn get_inner_mut(&mut self) -> &mut ThrillerNodeInner {
+        &mut self.inner
+    }
+
+    pub(crate) fn set_inner(&mut self, inner: ThrillerNodeInner) {
+        *self.inner = inner;
+    }
+
     pub(crate) fn add_in_edge(&mut self, edge: Rc<ThrillerEdge>) {
         self.in_edges.push(edge);
     }
@@ -99,6 +114,16 @@ impl ThrillerNode {
     pub(crate) fn inc_in_degrees(&mut self) {
         self.in_degrees += 1;
     }
+
+    /// Drop all connections, so that the graph can be connected again
+    /// after it was rewritten.
+    pub(crate) fn reset_connections(&mut self) {
+        self.in_edges.clear();
+        self.out_edges.clear();
+        self.prevs.clear();
+        self.nexts.clear();
+        self.in_degrees = 0;
+    }
 }
 
 impl Task for ThrillerNode {
@@ -1,9 +1,12 @@
 use super::GraphPass;
-use crate::{dataflow::ThrillerGraph, BufType, ThrillerNodeInner};
+use crate::kernels::layout::Layout as LayoutPrimitive;
+use crate::{dataflow::ThrillerGraph, BufType, Buffer, ThrillerNodeInner};
 
 /// AllocateVar
 pub struct AllocateVar {
     code: String,
+    /// Buffer ids and stage counts of software pipelined shared tiles.
+    stages: Vec<(usize, usize)>,
 }
 
 impl AllocateVar {
@@ -11,6 +14,7 @@ impl AllocateVar {
     pub fn new() -> Self {
         Self {
             code: String::new(),
+            stages: vec![],
         }
     }
 
@@ -18,10 +22,62 @@ impl AllocateVar {
     pub fn code(&self) -> String {
         self.code.clone()
     }
+
+    /// Declare the tile type of a buffer with its element type and layout.
+    fn allocate_layout(&mut self, prefix: &str, tile: &str, buf: &Buffer) {
+        self.code += format!(
+            "using {prefix}{name} = {tile}<{dtype}, {layout}>;\n",
+            prefix = prefix,
+            name = buf.get_name(),
+            tile = tile,
+            dtype = buf.get_dtype(),
+            layout = LayoutPrimitive::emit_layout(buf.get_shape())
+        )
+        .as_str();
+    }
+
+    /// Build a shared tile on the pointer `{name}_ptr` planned by
+    /// [`crate::SharedMemoryPlanner`]. Pipelined tiles are allocated once per
+    /// stage, one stage after the other.
+    fn allocate_shared(&mut self, buf: &Buffer) {
+        let name = buf.get_name();
+        match self.stages.iter().find(|(id, _)| *id == buf.get_id()) {
+            Some((_, stages)) => {
+                let stride = buf.get_shape().size_in_bytes(buf.get_dtype())
+                    / buf.get_dtype().size_in_bytes();
+                let tiles = (0..*stages)
+                    .map(|stage| format!("Shared{name}({name}_ptr + {})", stage * stride))
+                    .collect::<Vec<_>>();
+                self.code += format!(
+                    "Shared{name} {name}[{stages}] = {{{}}};\n",
+                    tiles.join(", ")
+                )
+                .as_str();
+            }
+            None => self.code += format!("Shared{name} {name}({name}_ptr);\n").as_str(),
+        }
+    }
+
+    /// Collect the pipelined shared tiles of all blocks in the graph, which
+    /// may be declared outside of the block loading them.
+    fn collect_stages(&mut self, graph: &ThrillerGraph) {
+        for node in &graph.nodes {
+            if let ThrillerNodeInner::Block(block) = node.borrow().get_inner() {
+                for buf in block.get_pipelined_buffers() {
+                    if !self.stages.iter().any(|(id, _)| *id == buf.get_id()) {
+                        self.stages.push((buf.get_id(), block.get_stages()));
+                    }
+                }
+                self.collect_stages(&block.subgraph.borrow());
+            }
+        }
+    }
 }
 
 impl GraphPass for AllocateVar {
     fn run(&mut self, graph: &mut ThrillerGraph) {
+        self.collect_stages(graph);
+
         // Transver the graph and allocate variables.
         for node in &graph.nodes {
             let node = node.borrow();
@@ -31,16 +87,22 @@ impl GraphPass for AllocateVar {
                     let btype = buf.get_typing();
                     match btype {
                         BufType::GlobalTile => {
+                            self.allocate_layout("Global", "GlobalTile", buf);
                             self.code +=
                                 format!("Global{} {};\n", buf.get_name(), buf.get_name()).as_str();
                         }
 
                         &BufType::SharedTile => {
-                            self.code +=
-                                format!("Shared{} {};\n", buf.get_name(), buf.get_name()).as_str();
+                            self.allocate_layout("Shared", "SharedTile", buf);
+                            self.allocate_shared(buf);
                         }
 
                         &BufType::RegTile | &BufType::RegVec => {
+                            let tile = match btype {
+                                BufType::RegVec => "RegVec",
+                                _ => "RegTile",
+                            };
+                            self.allocate_layout("Reg", tile, buf);
                             self.code +=
                                 format!("Reg{} {};\n", buf.get_name(), buf.get_name()).as_str();
                         }
@@ -0,0 +1,209 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::GraphPass;
+use crate::dataflow::{ReductionAnalysis, ThrillerGraph};
+use crate::{
+    AttachedEdge, MemoryLevel, ThrillerBlock, ThrillerError, ThrillerNode, ThrillerNodeInner,
+    ThrillerResult,
+};
+
+type NodeRef = Rc<RefCell<ThrillerNode>>;
+
+/// Shared and global buffers read and written by a task.
+///
+/// Register tiles are private to each thread and never need a barrier,
+/// while shared and global tiles are accessed by the whole thread block.
+#[derive(Default)]
+struct BufferAccesses {
+    reads: Vec<usize>,
+    writes: Vec<usize>,
+}
+
+impl BufferAccesses {
+    fn add_edges(&mut self, edges: &[Rc<AttachedEdge>]) {
+        for edge in edges {
+            if edge.src.get_typing().get_memory_level() != MemoryLevel::Register {
+                self.reads.push(edge.src.get_id());
+            }
+            if edge.dst.get_typing().get_memory_level() != MemoryLevel::Register {
+                self.writes.push(edge.dst.get_id());
+            }
+        }
+    }
+
+    fn add_block(&mut self, block: &ThrillerBlock) {
+        self.add_edges(&block.inputs);
+        self.add_edges(&block.outputs);
+        for node in block.subgraph.borrow().nodes.iter() {
+            self.add_node(node);
+        }
+    }
+
+    fn add_node(&mut self, node: &NodeRef) {
+        if let ThrillerNodeInner::Block(block) = node.borrow().get_inner() {
+            self.add_block(block);
+        }
+    }
+
+    /// Whether `later` reads what is written here, or overwrites a shared
+    /// tile read here.
+    fn conflicts_with(&self, later: &BufferAccesses) -> bool {
+        self.writes
+            .iter()
+            .any(|id| later.reads.contains(id) || later.writes.contains(id))
+            || self.reads.iter().any(|id| later.writes.contains(id))
+    }
+}
+
+/// [`BarrierElimination`] removes `__syncthreads()` barriers emitted by
+/// [`ThrillerBlock`]s that are not needed.
+///
+/// Each buffer is classified by the [`MemoryLevel`] it lives in. The barrier
+/// after a loop nest is removed when no following task accesses the shared
+/// or global tiles it guards, which includes barriers guarding register
+/// tiles only. A barrier is also removed when it directly follows another
+/// one, i.e. when the loop body already ends with a barrier.
+///
+/// Inside a loop nest, every task of the enclosing body and the loads and
+/// stores of the enclosing block may run after a task in the next
+/// iteration, so they are all treated as following tasks.
+pub struct BarrierElimination {
+    removed: usize,
+    error: Option<ThrillerError>,
+}
+
+impl BarrierElimination {
+    #[doc(hidden)]
+    pub fn new() -> Self {
+        Self {
+            removed: 0,
+            error: None,
+        }
+    }
+
+    /// Number of removed barriers.
+    pub fn removed(&self) -> usize {
+        self.removed
+    }
+
+    /// The number of removed barriers, or the error that stopped the pass,
+    /// e.g. [`ThrillerError::GraphCycle`] if the graph or a loop body is
+    /// not acyclic.
+    pub fn check(&self) -> ThrillerResult<usize> {
+        match &self.error {
+            Some(error) => Err(error.clone()),
+            None => Ok(self.removed),
+        }
+    }
+
+    fn get_block(node: &NodeRef) -> Option<Rc<ThrillerBlock>> {
+        match node.borrow().get_inner() {
+            ThrillerNodeInner::Block(block) => Some(block.clone()),
+            _ => None,
+        }
+    }
+
+    /// Whether the emitted code of the block ends with a barrier.
+    fn ends_with_barrier(block: &ThrillerBlock) -> bool {
+        let reduction = ReductionAnalysis::new(block);
+        block.has_loop_barrier()
+            && (0..block.outputs.len()).all(|index| reduction.get_store_depth(index) != 0)
+    }
+
+    /// Whether the innermost loop body of the block ends with a barrier.
+    fn body_ends_with_barrier(block: &ThrillerBlock) -> bool {
+        if block.has_stage_barrier() {
+            return true;
+        }
+
+        let Ok(nodes) = block.subgraph.borrow().topo_sort() else {
+            return false;
+        };
+        nodes
+            .last()
+            .and_then(Self::get_block)
+            .is_some_and(|last| Self::ends_with_barrier(&last))
+    }
+
+    /// Remove the barriers of `block`, given the accesses of the tasks that
+    /// may follow it.
+    fn eliminate(&mut self, block: &ThrillerBlock, later: &BufferAccesses) -> ThrillerBlock {
+        let mut block = block.clone();
+
+        // The loads of the next iteration directly follow a barrier closing
+        // the body.
+        if block.has_stage_barrier() {
+            block.stage_barrier = false;
+            if Self::body_ends_with_barrier(&block) {
+                self.removed += 1;
+            } else {
+                block.stage_barrier = true;
+            }
+        }
+
+        if block.has_loop_barrier() {
+            let reduction = ReductionAnalysis::new(&block);
+            let stores_in_loop =
+                (0..block.outputs.len()).any(|index| reduction.get_store_depth(index) != 0);
+
+            let mut accesses = BufferAccesses::default();
+            accesses.add_block(&block);
+
+            if !accesses.conflicts_with(later)
+                || (!block.ivars.is_empty()
+                    && !stores_in_loop
+                    && Self::body_ends_with_barrier(&block))
+            {
+                block.loop_barrier = false;
+                self.removed += 1;
+            }
+        }
+
+        block
+    }
+
+    fn run_in(&mut self, graph: &ThrillerGraph, enclosing: Option<&ThrillerBlock>) {
+        let nodes = match graph.topo_sort() {
+            Ok(nodes) => nodes,
+            Err(error) => {
+                self.error = Some(error);
+                return;
+            }
+        };
+
+        for (index, node) in nodes.iter().enumerate() {
+            let Some(block) = Self::get_block(node) else {
+                continue;
+            };
+
+            // Eliminate barriers in the loop body first.
+            self.run_in(&block.subgraph.borrow(), Some(&block));
+            if self.error.is_some() {
+                return;
+            }
+
+            let mut later = BufferAccesses::default();
+            match enclosing {
+                Some(enclosing) => {
+                    later.add_edges(&enclosing.inputs);
+                    later.add_edges(&enclosing.outputs);
+                    nodes.iter().for_each(|node| later.add_node(node));
+                }
+                None => nodes[index + 1..]
+                    .iter()
+                    .for_each(|node| later.add_node(node)),
+            }
+
+            let block = self.eliminate(&block, &later);
+            node.borrow_mut()
+                .set_inner(ThrillerNodeInner::Block(Rc::new(block)));
+        }
+    }
+}
+
+impl GraphPass for BarrierElimination {
+    fn run(&mut self, graph: &mut ThrillerGraph) {
+        self.run_in(graph, None);
+    }
+}
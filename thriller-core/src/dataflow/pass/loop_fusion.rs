@@ -0,0 +1,422 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::{rewrite_access_maps, GraphPass};
+use crate::dataflow::{DependenceAnalysis, Distance, MemoryAccess, ThrillerGraph};
+use crate::{
+    AttachedEdge, BufType, Task, ThrillerBlock, ThrillerEdge, ThrillerNode, ThrillerNodeInner,
+    TileCopy,
+};
+
+type NodeRef = Rc<RefCell<ThrillerNode>>;
+
+/// An intermediate buffer written by a producer block and read by a
+/// consumer block.
+struct Intermediate {
+    store: Rc<AttachedEdge>,
+    load: Rc<AttachedEdge>,
+    /// Whether other nodes read the buffer, so the store has to be kept.
+    shared: bool,
+}
+
+/// [`LoopFusion`] fuses sibling [`ThrillerBlock`]s of a [`ThrillerGraph`].
+///
+/// A producer block and a consumer block are fused when:
+/// - their loops have the same iteration domains,
+/// - the consumer loads a global or shared tile stored by the producer,
+///   from a register tile into a register tile,
+/// - the tile is loaded in the same iteration it is stored, i.e. the
+///   dependence distance is zero for every loop,
+/// - no other node of the graph sits between the two blocks.
+///
+/// The fused block keeps the loops of the producer. Its inputs, outputs and
+/// subgraph are the ones of both blocks, except that the round-trip through
+/// the intermediate tile is replaced by a register [`TileCopy`]. The store
+/// is kept if the intermediate tile is read by another block.
+pub struct LoopFusion {
+    fused: Vec<String>,
+}
+
+impl LoopFusion {
+    #[doc(hidden)]
+    pub fn new() -> Self {
+        Self { fused: vec![] }
+    }
+
+    /// Names of the fused blocks.
+    pub fn fused(&self) -> &Vec<String> {
+        &self.fused
+    }
+
+    fn get_block(node: &NodeRef) -> Option<Rc<ThrillerBlock>> {
+        match node.borrow().get_inner() {
+            ThrillerNodeInner::Block(block) => Some(block.clone()),
+            _ => None,
+        }
+    }
+
+    fn same_domains(producer: &ThrillerBlock, consumer: &ThrillerBlock) -> bool {
+        producer.ivars.len() == consumer.ivars.len()
+            && producer
+                .ivars
+                .iter()
+                .zip(consumer.ivars.iter())
+                .all(|(p, c)| {
+                    let (p_lower, p_upper) = p.get_domain();
+                    let (c_lower, c_upper) = c.get_domain();
+                    p_lower.to_string() == c_lower.to_string()
+                        && p_upper.to_string() == c_upper.to_string()
+                })
+    }
+
+    /// Whether `to` can be reached from `from` through a path of at least
+    /// two edges not going through `skip`. A direct edge is the dependence
+    /// being fused and does not count.
+    fn reachable(from: &NodeRef, to: &NodeRef, skip: &[NodeRef]) -> bool {
+        let mut stack = vec![from.clone()];
+        let mut visited: Vec<NodeRef> = vec![];
+
+        while let Some(node) = stack.pop() {
+            for next in node.borrow().get_nexts() {
+                if Rc::ptr_eq(next, to) {
+                    if Rc::ptr_eq(&node, from) {
+                        continue;
+                    }
+                    return true;
+                }
+                if skip.iter().any(|n| Rc::ptr_eq(n, next))
+                    || visited.iter().any(|n| Rc::ptr_eq(n, next))
+                {
+                    continue;
+                }
+                visited.push(next.clone());
+                stack.push(next.clone());
+            }
+        }
+
+        false
+    }
+
+    /// Buffer nodes of the intermediate tiles only connected to the two blocks.
+    fn intermediate_nodes(
+        graph: &ThrillerGraph,
+        intermediates: &[Intermediate],
+        blocks: &[NodeRef],
+    ) -> Vec<NodeRef> {
+        graph
+            .nodes
+            .iter()
+            .filter(|node| {
+                let node = node.borrow();
+                let ThrillerNodeInner::Buffer(buf) = node.get_inner() else {
+                    return false;
+                };
+                intermediates
+                    .iter()
+                    .any(|i| !i.shared && i.store.dst.get_id() == buf.get_id())
+                    && node
+                        .get_prevs()
+                        .iter()
+                        .chain(node.get_nexts().iter())
+                        .all(|n| blocks.iter().any(|b| Rc::ptr_eq(b, n)))
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn find_intermediates(
+        graph: &ThrillerGraph,
+        producer_node: &NodeRef,
+        consumer_node: &NodeRef,
+    ) -> Option<Vec<Intermediate>> {
+        let producer = Self::get_block(producer_node)?;
+        let consumer = Self::get_block(consumer_node)?;
+
+        if !Self::same_domains(&producer, &consumer) {
+            return None;
+        }
+
+        let analysis = DependenceAnalysis::new(producer.ivars.clone());
+        let mut intermediates = vec![];
+
+        for store in producer.outputs.iter() {
+            for load in consumer.inputs.iter() {
+                if store.dst.get_id() != load.src.get_id() {
+                    continue;
+                }
+
+                if !matches!(
+                    store.dst.get_typing(),
+                    BufType::GlobalTile | BufType::SharedTile
+                ) || *store.src.get_typing() != BufType::RegTile
+                    || *load.dst.get_typing() != BufType::RegTile
+                {
+                    return None;
+                }
+
+                // The tile has to be loaded in the same iteration it is stored.
+                let access = load
+                    .get_access()
+                    .substitute_ivars(&consumer.ivars, &producer.ivars);
+                let load_access = load.with_access(Rc::new(access));
+                let dependence = analysis.analyze(
+                    &MemoryAccess::target_of(store),
+                    &MemoryAccess::source_of(&load_access),
+                )?;
+                if dependence
+                    .get_distance()
+                    .iter()
+                    .any(|distance| *distance != Distance::Exact(0))
+                {
+                    return None;
+                }
+
+                let shared = graph.nodes.iter().any(|node| {
+                    !Rc::ptr_eq(node, consumer_node)
+                        && Self::get_block(node).is_some_and(|block| {
+                            block
+                                .inputs
+                                .iter()
+                                .any(|edge| edge.src.get_id() == store.dst.get_id())
+                        })
+                });
+
+                intermediates.push(Intermediate {
+                    store: store.clone(),
+                    load: load.clone(),
+                    shared,
+                });
+            }
+        }
+
+        if intermediates.is_empty() {
+            return None;
+        }
+
+        // Fusing must not create a cycle through other nodes.
+        let blocks = [producer_node.clone(), consumer_node.clone()];
+        let skip = Self::intermediate_nodes(graph, &intermediates, &blocks);
+        if Self::reachable(
+            producer_node,
+            consumer_node,
+            &[&skip[..], &blocks[1..]].concat(),
+        ) || Self::reachable(consumer_node, producer_node, &[])
+        {
+            return None;
+        }
+
+        Some(intermediates)
+    }
+
+    /// Build the fused block. The two blocks are left untouched, their
+    /// nodes are only rewired by [`Self::rewrite_graph`] once the fusion is
+    /// committed.
+    fn fuse_blocks(
+        producer: &ThrillerBlock,
+        consumer: &ThrillerBlock,
+        intermediates: &[Intermediate],
+    ) -> ThrillerBlock {
+        // The consumer now iterates over the loops of the producer.
+        let remap = |edge: &Rc<AttachedEdge>| {
+            let access = edge
+                .get_access()
+                .substitute_ivars(&consumer.ivars, &producer.ivars);
+            Rc::new(edge.with_access(Rc::new(access)))
+        };
+
+        let is_load =
+            |edge: &Rc<AttachedEdge>| intermediates.iter().any(|i| Rc::ptr_eq(&i.load, edge));
+        let is_dropped_store = |edge: &Rc<AttachedEdge>| {
+            intermediates
+                .iter()
+                .any(|i| !i.shared && Rc::ptr_eq(&i.store, edge))
+        };
+
+        let inputs = producer
+            .inputs
+            .iter()
+            .cloned()
+            .chain(consumer.inputs.iter().filter(|e| !is_load(e)).map(remap))
+            .collect();
+        let outputs = producer
+            .outputs
+            .iter()
+            .filter(|e| !is_dropped_store(e))
+            .cloned()
+            .chain(consumer.outputs.iter().map(remap))
+            .collect();
+
+        let producer_graph = producer.subgraph.borrow();
+        let consumer_graph = consumer.subgraph.borrow();
+
+        let copies = intermediates
+            .iter()
+            .filter(|i| i.store.src.get_id() != i.load.dst.get_id())
+            .map(|i| {
+                let copy = TileCopy::new(i.store.src.clone(), i.load.dst.clone());
+                Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::Op(
+                    Box::new(copy),
+                ))))
+            })
+            .collect::<Vec<_>>();
+
+        // The forwarded tiles are copied after the producer body and
+        // before the consumer body.
+        let sinks = producer_graph
+            .nodes
+            .iter()
+            .filter(|node| {
+                !producer_graph
+                    .edges
+                    .iter()
+                    .any(|edge| Rc::ptr_eq(&edge.get_src(), node))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        let sources = consumer_graph
+            .nodes
+            .iter()
+            .filter(|node| {
+                !consumer_graph
+                    .edges
+                    .iter()
+                    .any(|edge| Rc::ptr_eq(&edge.get_dst(), node))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        let connect = |srcs: &[NodeRef], dsts: &[NodeRef]| {
+            srcs.iter()
+                .flat_map(|src| {
+                    dsts.iter()
+                        .map(|dst| Rc::new(ThrillerEdge::new(src.clone(), dst.clone())))
+                })
+                .collect::<Vec<_>>()
+        };
+        let ordering = if copies.is_empty() {
+            connect(&sinks, &sources)
+        } else {
+            [connect(&sinks, &copies), connect(&copies, &sources)].concat()
+        };
+
+        let mut subgraph = ThrillerGraph::new();
+        subgraph.add_nodes(producer_graph.nodes.clone());
+        subgraph.add_nodes(copies);
+        subgraph.add_nodes(consumer_graph.nodes.clone());
+        subgraph.add_edges(producer_graph.edges.clone());
+        subgraph.add_edges(consumer_graph.edges.clone());
+        subgraph.add_edges(ordering);
+
+        ThrillerBlock::new(
+            inputs,
+            outputs,
+            Rc::new(RefCell::new(subgraph)),
+            producer.ivars.clone(),
+        )
+    }
+
+    /// Replace the producer by the fused block and drop the consumer and
+    /// the buffer nodes of the dropped intermediate tiles.
+    fn rewrite_graph(
+        graph: &mut ThrillerGraph,
+        producer_node: &NodeRef,
+        consumer_node: &NodeRef,
+        intermediates: &[Intermediate],
+        fused: ThrillerBlock,
+    ) {
+        // The ops of the consumer now iterate over the loops of the producer.
+        let producer = Self::get_block(producer_node).unwrap();
+        let consumer = Self::get_block(consumer_node).unwrap();
+        rewrite_access_maps(&consumer.subgraph.borrow(), &|access| {
+            access.substitute_ivars(&consumer.ivars, &producer.ivars)
+        });
+
+        {
+            let mut subgraph = fused.subgraph.borrow_mut();
+            for node in subgraph.nodes.iter() {
+                node.borrow_mut().reset_connections();
+            }
+            subgraph.connect();
+        }
+
+        let blocks = [producer_node.clone(), consumer_node.clone()];
+        let mut removed = Self::intermediate_nodes(graph, intermediates, &blocks);
+        removed.push(consumer_node.clone());
+        let is_removed = |node: &NodeRef| removed.iter().any(|n| Rc::ptr_eq(n, node));
+
+        let mut edges: Vec<Rc<ThrillerEdge>> = vec![];
+        for edge in graph.edges.iter() {
+            let redirect = |node: NodeRef| {
+                if Rc::ptr_eq(&node, consumer_node) {
+                    producer_node.clone()
+                } else {
+                    node
+                }
+            };
+            let (src, dst) = (edge.get_src(), edge.get_dst());
+            if (is_removed(&src) && !Rc::ptr_eq(&src, consumer_node))
+                || (is_removed(&dst) && !Rc::ptr_eq(&dst, consumer_node))
+            {
+                continue;
+            }
+
+            let (src, dst) = (redirect(src), redirect(dst));
+            let exists = edges
+                .iter()
+                .any(|e| Rc::ptr_eq(&e.get_src(), &src) && Rc::ptr_eq(&e.get_dst(), &dst));
+            if !Rc::ptr_eq(&src, &dst) && !exists {
+                edges.push(Rc::new(ThrillerEdge::new(src, dst)));
+            }
+        }
+
+        graph.nodes.retain(|node| !is_removed(node));
+        graph.edges = edges;
+
+        producer_node
+            .borrow_mut()
+            .set_inner(ThrillerNodeInner::Block(Rc::new(fused)));
+
+        for node in graph.nodes.iter() {
+            node.borrow_mut().reset_connections();
+        }
+        graph.connect();
+    }
+
+    fn fuse_once(&mut self, graph: &mut ThrillerGraph) -> bool {
+        for producer_node in graph.nodes.clone().iter() {
+            for consumer_node in graph.nodes.clone().iter() {
+                if Rc::ptr_eq(producer_node, consumer_node) {
+                    continue;
+                }
+
+                let Some(intermediates) =
+                    Self::find_intermediates(graph, producer_node, consumer_node)
+                else {
+                    continue;
+                };
+
+                let producer = Self::get_block(producer_node).unwrap();
+                let consumer = Self::get_block(consumer_node).unwrap();
+                let fused = Self::fuse_blocks(&producer, &consumer, &intermediates);
+
+                self.fused.push(fused.get_name());
+                Self::rewrite_graph(graph, producer_node, consumer_node, &intermediates, fused);
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+impl GraphPass for LoopFusion {
+    fn run(&mut self, graph: &mut ThrillerGraph) {
+        // Fuse the nested loops first.
+        for node in graph.nodes.iter() {
+            if let Some(block) = Self::get_block(node) {
+                self.run(&mut block.subgraph.borrow_mut());
+            }
+        }
+
+        while self.fuse_once(graph) {}
+    }
+}
@@ -0,0 +1,149 @@
+use std::rc::Rc;
+
+use super::GraphPass;
+use crate::dataflow::{DependenceAnalysis, ThrillerGraph};
+use crate::{
+    warn, AccessMap, AttachedEdge, IterationVar, Task, ThrillerBlock, ThrillerNodeInner, Var,
+};
+
+/// [`LoopInterchange`] reorders the loops of [`ThrillerBlock`]s.
+///
+/// Every block whose iteration variables are named exactly like the given
+/// order, in any order, gets its loops reordered, and the columns of the
+/// access matrices on its [`AttachedEdge`]s are permuted accordingly.
+/// A block is left untouched if the interchange would reverse one of the
+/// dependences between its loads, ops and stores found by
+/// [`DependenceAnalysis`].
+pub struct LoopInterchange {
+    order: Vec<String>,
+    interchanged: Vec<String>,
+    rejected: Vec<String>,
+}
+
+impl LoopInterchange {
+    /// Create a new pass reordering loops into `order`, from the outermost
+    /// to the innermost iteration variable name.
+    pub fn new(order: &[&str]) -> Self {
+        Self {
+            order: order.iter().map(|name| name.to_string()).collect(),
+            interchanged: vec![],
+            rejected: vec![],
+        }
+    }
+
+    /// Names of the blocks whose loops were reordered.
+    pub fn interchanged(&self) -> &Vec<String> {
+        &self.interchanged
+    }
+
+    /// Names of the blocks where the interchange was illegal.
+    pub fn rejected(&self) -> &Vec<String> {
+        &self.rejected
+    }
+
+    /// `order[i]` is the current loop of the block that becomes loop `i`,
+    /// `None` if the block does not iterate over the requested variables.
+    fn permutation(&self, block: &ThrillerBlock) -> Option<Vec<usize>> {
+        if block.ivars.len() != self.order.len() {
+            return None;
+        }
+
+        let order = self
+            .order
+            .iter()
+            .map(|name| block.ivars.iter().position(|ivar| ivar.get_name() == name))
+            .collect::<Option<Vec<_>>>()?;
+
+        let mut sorted = order.clone();
+        sorted.sort();
+        sorted.dedup();
+        (sorted.len() == order.len()).then_some(order)
+    }
+
+    /// Permute the columns of the block's iteration variables in `access`
+    /// to follow `ivars`, keeping the columns of outer loops in place.
+    fn reorder_access(access: &AccessMap, ivars: &[Rc<IterationVar>]) -> AccessMap {
+        let access_ivars = access.get_iter_vars();
+        let position = |ivar: &Rc<IterationVar>| {
+            ivars
+                .iter()
+                .position(|block_ivar| block_ivar.get_id() == ivar.get_id())
+        };
+
+        let mut block_columns = (0..access_ivars.len())
+            .filter(|&col| position(&access_ivars[col]).is_some())
+            .collect::<Vec<_>>();
+        block_columns.sort_by_key(|&col| position(&access_ivars[col]));
+
+        let mut block_columns = block_columns.into_iter();
+        let order = (0..access_ivars.len())
+            .map(|col| match position(&access_ivars[col]) {
+                Some(_) => block_columns.next().unwrap(),
+                None => col,
+            })
+            .collect::<Vec<_>>();
+
+        access.permute_ivars(&order)
+    }
+
+    fn interchange(&self, block: &ThrillerBlock, order: &[usize]) -> ThrillerBlock {
+        let mut block = block.clone();
+        block.ivars = order.iter().map(|&i| block.ivars[i].clone()).collect();
+
+        let reorder = |edges: &Vec<Rc<AttachedEdge>>| {
+            edges
+                .iter()
+                .map(|edge| {
+                    let access = Self::reorder_access(edge.get_access(), &block.ivars);
+                    Rc::new(edge.with_access(Rc::new(access)))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        block.inputs = reorder(&block.inputs);
+        block.outputs = reorder(&block.outputs);
+        block
+    }
+}
+
+impl GraphPass for LoopInterchange {
+    fn run(&mut self, graph: &mut ThrillerGraph) {
+        for node in &graph.nodes {
+            let block = match node.borrow().get_inner() {
+                ThrillerNodeInner::Block(block) => block.clone(),
+                _ => continue,
+            };
+
+            // Recursively interchange loops in the block.
+            self.run(&mut block.subgraph.borrow_mut());
+
+            let Some(order) = self.permutation(&block) else {
+                continue;
+            };
+
+            if order.iter().enumerate().all(|(i, &j)| i == j) {
+                continue;
+            }
+
+            let analysis = DependenceAnalysis::from_block(&block);
+            if !analysis
+                .get_dependences()
+                .iter()
+                .all(|dependence| dependence.is_preserved_by(&order))
+            {
+                warn!(
+                    "Loop interchange of {} into {:?} violates a dependence.",
+                    block.get_name(),
+                    self.order
+                );
+                self.rejected.push(block.get_name());
+                continue;
+            }
+
+            let interchanged = self.interchange(&block, &order);
+            self.interchanged.push(interchanged.get_name());
+            node.borrow_mut()
+                .set_inner(ThrillerNodeInner::Block(Rc::new(interchanged)));
+        }
+    }
+}
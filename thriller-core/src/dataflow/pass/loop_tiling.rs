@@ -0,0 +1,138 @@
+use std::rc::Rc;
+
+use super::{rewrite_access_maps, GraphPass};
+use crate::dataflow::ThrillerGraph;
+use crate::{
+    warn, AccessMap, AttachedEdge, IterationBound, IterationVar, Task, ThrillerBlock,
+    ThrillerNodeInner, Var,
+};
+
+/// [`LoopTiling`] strip-mines a loop of [`ThrillerBlock`]s.
+///
+/// The iteration variable `i` with domain `[lower, upper)` is split into an
+/// outer tile loop `i_o` over `[0, (upper - lower) / factor)` and an inner
+/// point loop `i_i` over `[0, factor)`, with `i = lower + factor * i_o + i_i`.
+/// The access maps on the block's [`crate::AttachedEdge`]s and those of the
+/// ops and blocks nested in it are rewritten accordingly.
+///
+/// Only fixed domains evenly divided by the tile factor are tiled. Running
+/// the pass again on `i_i` derives another level of tiling.
+pub struct LoopTiling {
+    ivar: String,
+    factor: usize,
+    tiled: Vec<String>,
+    rejected: Vec<String>,
+}
+
+impl LoopTiling {
+    /// Create a new pass tiling the loops over the iteration variable named
+    /// `ivar` by `factor`.
+    pub fn new(ivar: &str, factor: usize) -> Self {
+        Self {
+            ivar: ivar.to_string(),
+            factor,
+            tiled: vec![],
+            rejected: vec![],
+        }
+    }
+
+    /// Names of the blocks whose loop was tiled.
+    pub fn tiled(&self) -> &Vec<String> {
+        &self.tiled
+    }
+
+    /// Names of the blocks whose loop could not be tiled.
+    pub fn rejected(&self) -> &Vec<String> {
+        &self.rejected
+    }
+
+    /// The lower bound and trip count of the tiled loop, `None` if it cannot
+    /// be tiled by `factor`.
+    fn tile_domain(&self, ivar: &IterationVar) -> Option<(usize, usize)> {
+        let (IterationBound::Fixed(lower), IterationBound::Fixed(upper)) = ivar.get_domain() else {
+            return None;
+        };
+
+        let extent = upper.checked_sub(*lower)?;
+        (self.factor != 0 && extent % self.factor == 0).then_some((*lower, extent / self.factor))
+    }
+
+    fn tile(
+        &self,
+        block: &ThrillerBlock,
+        position: usize,
+        lower: usize,
+        trips: usize,
+    ) -> ThrillerBlock {
+        let ivar = block.ivars[position].clone();
+        let outer = Rc::new(IterationVar::new(
+            &format!("{}_o", ivar.get_name()),
+            (IterationBound::Fixed(0), IterationBound::Fixed(trips)),
+        ));
+        let inner = Rc::new(IterationVar::new(
+            &format!("{}_i", ivar.get_name()),
+            (IterationBound::Fixed(0), IterationBound::Fixed(self.factor)),
+        ));
+
+        let split = |access: &AccessMap| {
+            access.split_ivar(&ivar, lower, self.factor, outer.clone(), inner.clone())
+        };
+
+        rewrite_access_maps(&block.subgraph.borrow(), &split);
+
+        let mut block = block.clone();
+        block
+            .ivars
+            .splice(position..=position, [outer.clone(), inner.clone()]);
+
+        let rewrite = |edges: &Vec<_>| {
+            edges
+                .iter()
+                .map(|edge: &Rc<AttachedEdge>| {
+                    Rc::new(edge.with_access(Rc::new(split(edge.get_access()))))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        block.inputs = rewrite(&block.inputs);
+        block.outputs = rewrite(&block.outputs);
+        block
+    }
+}
+
+impl GraphPass for LoopTiling {
+    fn run(&mut self, graph: &mut ThrillerGraph) {
+        for node in &graph.nodes {
+            let block = match node.borrow().get_inner() {
+                ThrillerNodeInner::Block(block) => block.clone(),
+                _ => continue,
+            };
+
+            let Some(position) = block
+                .ivars
+                .iter()
+                .position(|ivar| *ivar.get_name() == self.ivar)
+            else {
+                // Recursively tile loops in the block.
+                self.run(&mut block.subgraph.borrow_mut());
+                continue;
+            };
+
+            let Some((lower, trips)) = self.tile_domain(&block.ivars[position]) else {
+                warn!(
+                    "Loop {} of {} cannot be tiled by {}.",
+                    self.ivar,
+                    block.get_name(),
+                    self.factor
+                );
+                self.rejected.push(block.get_name());
+                continue;
+            };
+
+            let tiled = self.tile(&block, position, lower, trips);
+            self.tiled.push(tiled.get_name());
+            node.borrow_mut()
+                .set_inner(ThrillerNodeInner::Block(Rc::new(tiled)));
+        }
+    }
+}
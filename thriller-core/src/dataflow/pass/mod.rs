@@ -1,14 +1,65 @@
+use std::rc::Rc;
+
 use super::ThrillerGraph;
+use crate::{AccessMap, AttachedEdge, ThrillerNodeInner};
 
 mod allocate_edge;
 mod allocate_var;
+mod barrier_elimination;
 mod gen_iterator;
+mod loop_fusion;
+mod loop_interchange;
+mod loop_tiling;
+mod shape_validation;
+mod shared_memory_planner;
 
 pub use allocate_edge::AllocateEdge;
 pub use allocate_var::AllocateVar;
+pub use barrier_elimination::BarrierElimination;
+pub use loop_fusion::LoopFusion;
+pub use loop_interchange::LoopInterchange;
+pub use loop_tiling::LoopTiling;
+pub use shape_validation::{Diagnostic, ShapeValidation};
+pub use shared_memory_planner::SharedMemoryPlanner;
 
 /// A trait for graph passes.
 pub trait GraphPass {
     /// Run the pass on the graph.
     fn run(&mut self, graph: &mut ThrillerGraph);
 }
+
+/// Rewrite the [`AccessMap`]s of all ops and nested blocks in `graph`,
+/// used by loop transformations to follow changes of the loop nest.
+pub(crate) fn rewrite_access_maps(
+    graph: &ThrillerGraph,
+    rewrite: &dyn Fn(&AccessMap) -> AccessMap,
+) {
+    for node in graph.nodes.iter() {
+        let mut node = node.borrow_mut();
+        match node.get_inner_mut() {
+            ThrillerNodeInner::Op(op) => {
+                if let Some(access) = op.get_access_map() {
+                    let access = Rc::new(rewrite(access));
+                    op.set_access_map(access);
+                }
+            }
+            ThrillerNodeInner::Block(block) => {
+                rewrite_access_maps(&block.subgraph.borrow(), rewrite);
+
+                let mut rewritten = (**block).clone();
+                let rewrite_edges = |edges: &Vec<_>| {
+                    edges
+                        .iter()
+                        .map(|edge: &Rc<AttachedEdge>| {
+                            Rc::new(edge.with_access(Rc::new(rewrite(edge.get_access()))))
+                        })
+                        .collect()
+                };
+                rewritten.inputs = rewrite_edges(&block.inputs);
+                rewritten.outputs = rewrite_edges(&block.outputs);
+                *block = Rc::new(rewritten);
+            }
+            ThrillerNodeInner::Buffer(_) => {}
+        }
+    }
+}
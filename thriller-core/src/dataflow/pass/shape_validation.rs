@@ -0,0 +1,171 @@
+use std::fmt::Display;
+
+use super::GraphPass;
+use crate::dataflow::ThrillerGraph;
+use crate::{AttachedEdge, Buffer, Dimension, ThrillerEdge, ThrillerError, ThrillerNodeInner};
+
+/// A [`Diagnostic`] is a problem found by [`ShapeValidation`] at a node or
+/// edge of the graph.
+#[derive(Debug)]
+pub struct Diagnostic {
+    location: String,
+    error: ThrillerError,
+    message: String,
+}
+
+impl Diagnostic {
+    /// Create a new diagnostic at the node or edge named `location`.
+    pub fn new(location: &str, error: ThrillerError, message: String) -> Self {
+        Diagnostic {
+            location: location.to_string(),
+            error,
+            message,
+        }
+    }
+
+    /// Get the name of the node or edge where the problem was found.
+    pub fn get_location(&self) -> &String {
+        &self.location
+    }
+
+    /// Get the kind of the problem.
+    pub fn get_error(&self) -> &ThrillerError {
+        &self.error
+    }
+
+    /// Get the description of the problem.
+    pub fn get_message(&self) -> &String {
+        &self.message
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+/// [`ShapeValidation`] checks that the operands of every op have compatible
+/// shapes and types, that the buffers connected by the [`AttachedEdge`]s of
+/// every [`crate::ThrillerBlock`] are tiles of each other of the same type,
+/// and that buffer nodes connected by a [`ThrillerEdge`] have the same shape
+/// and type.
+///
+/// The graph is not modified, the problems are collected as [`Diagnostic`]s.
+pub struct ShapeValidation {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl ShapeValidation {
+    #[doc(hidden)]
+    pub fn new() -> Self {
+        Self {
+            diagnostics: vec![],
+        }
+    }
+
+    /// The problems found in the graph.
+    pub fn diagnostics(&self) -> &Vec<Diagnostic> {
+        &self.diagnostics
+    }
+
+    /// Whether no problem was found.
+    pub fn is_valid(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    /// Check that the smaller of the source and destination buffers of
+    /// `edge` evenly tiles the larger one, aligning their trailing dimensions,
+    /// and that both have the same element type.
+    fn check_edge(&mut self, edge: &AttachedEdge) {
+        let location = format!("{} -> {}", edge.get_src_name(), edge.get_dst_name());
+        self.check_dtype(&location, &edge.src, &edge.dst);
+
+        let (src, dst) = (dims(&edge.src), dims(&edge.dst));
+
+        let rank = src.len().min(dst.len());
+        let src = &src[src.len() - rank..];
+        let dst = &dst[dst.len() - rank..];
+
+        for (axis, (&s, &d)) in src.iter().zip(dst.iter()).enumerate() {
+            let (large, small) = (s.max(d), s.min(d));
+            if small == 0 || large % small != 0 {
+                self.diagnostics.push(Diagnostic::new(
+                    &location,
+                    ThrillerError::ShapeMismatch,
+                    format!(
+                        "dimension {} of {:?} and {:?} do not tile each other",
+                        axis, src, dst
+                    ),
+                ));
+                return;
+            }
+        }
+    }
+
+    /// Check that the buffer nodes connected by `edge`, if both are buffers,
+    /// have the same shape and element type.
+    fn check_graph_edge(&mut self, edge: &ThrillerEdge) {
+        let (src, dst) = (edge.get_src(), edge.get_dst());
+        let (src, dst) = (src.borrow(), dst.borrow());
+        let (ThrillerNodeInner::Buffer(src), ThrillerNodeInner::Buffer(dst)) =
+            (src.get_inner(), dst.get_inner())
+        else {
+            return;
+        };
+
+        let location = format!("{} -> {}", src.get_name(), dst.get_name());
+        self.check_dtype(&location, src, dst);
+
+        if dims(src) != dims(dst) {
+            self.diagnostics.push(Diagnostic::new(
+                &location,
+                ThrillerError::ShapeMismatch,
+                format!("shapes {:?} and {:?} differ", dims(src), dims(dst)),
+            ));
+        }
+    }
+
+    fn check_dtype(&mut self, location: &str, src: &Buffer, dst: &Buffer) {
+        if src.get_dtype() != dst.get_dtype() {
+            self.diagnostics.push(Diagnostic::new(
+                location,
+                ThrillerError::DataTypeMismatch,
+                format!(
+                    "element types {} and {} differ",
+                    src.get_dtype(),
+                    dst.get_dtype()
+                ),
+            ));
+        }
+    }
+}
+
+fn dims(buf: &Buffer) -> &[usize] {
+    buf.get_shape().get_dims().slice()
+}
+
+impl GraphPass for ShapeValidation {
+    fn run(&mut self, graph: &mut ThrillerGraph) {
+        for edge in graph.edges.iter() {
+            self.check_graph_edge(edge);
+        }
+
+        for node in &graph.nodes {
+            match node.borrow().get_inner() {
+                ThrillerNodeInner::Op(op) => {
+                    self.diagnostics.extend(op.validate());
+                }
+                ThrillerNodeInner::Block(block) => {
+                    for edge in block.inputs.iter().chain(block.outputs.iter()) {
+                        self.check_edge(edge);
+                    }
+
+                    // Recursively validate the block.
+                    self.run(&mut block.subgraph.borrow_mut());
+                }
+                ThrillerNodeInner::Buffer(_) => {}
+            }
+        }
+    }
+}
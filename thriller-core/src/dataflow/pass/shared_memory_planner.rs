@@ -0,0 +1,268 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::GraphPass;
+use crate::dataflow::ThrillerGraph;
+use crate::{
+    error, AttachedEdge, BufType, Buffer, ThrillerBlock, ThrillerError, ThrillerNode,
+    ThrillerNodeInner, ThrillerResult,
+};
+
+type NodeRef = Rc<RefCell<ThrillerNode>>;
+
+/// Alignment in bytes of every shared buffer.
+const ALIGNMENT: usize = 128;
+
+/// A shared buffer with the range of top-level tasks it is live in.
+struct SharedBuffer {
+    buffer: Rc<Buffer>,
+    stages: usize,
+    first: usize,
+    last: usize,
+    offset: usize,
+}
+
+impl SharedBuffer {
+    /// Size in bytes of all stages, rounded up to the alignment.
+    fn size(&self) -> usize {
+        let size = self
+            .buffer
+            .get_shape()
+            .size_in_bytes(self.buffer.get_dtype())
+            * self.stages;
+        size.div_ceil(ALIGNMENT) * ALIGNMENT
+    }
+
+    fn overlaps(&self, other: &SharedBuffer) -> bool {
+        self.first <= other.last && other.first <= self.last
+    }
+}
+
+/// [`SharedMemoryPlanner`] places the [`BufType::SharedTile`] buffers of the
+/// graph into the dynamic shared memory buffer `shm` of the kernel.
+///
+/// Tasks are numbered in program order, recursing into the bodies of the
+/// loop nests: the loads of a block, the tasks of its subgraph, then its
+/// stores. A shared buffer is live from the first to the last task
+/// accessing it. As a loop body runs many times, buffers live before a loop
+/// and accessed in its body, as well as software pipelined buffers loaded
+/// ahead, stay live until the end of the loop. Buffers whose lifetimes do
+/// not overlap share the same bytes, larger buffers are placed first at the
+/// lowest free offset. Software pipelined buffers take one copy per stage.
+///
+/// The pass emits one pointer `shm + offset` per buffer, from which
+/// [`crate::AllocateVar`] builds the shared tiles, and reports the total
+/// size, which must not exceed the shared memory `limit` of one SM.
+pub struct SharedMemoryPlanner {
+    limit: usize,
+    buffers: Vec<SharedBuffer>,
+    total: usize,
+    code: String,
+    error: Option<ThrillerError>,
+}
+
+impl SharedMemoryPlanner {
+    /// Create a new planner for at most `limit` bytes of shared memory.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            buffers: vec![],
+            total: 0,
+            code: String::new(),
+            error: None,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn code(&self) -> String {
+        self.code.clone()
+    }
+
+    /// Total size in bytes of the shared memory buffer, to be passed to the
+    /// kernel launch.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Byte offset of `buffer` in the shared memory buffer.
+    pub fn get_offset(&self, buffer: &Buffer) -> Option<usize> {
+        self.buffers
+            .iter()
+            .find(|shared| shared.buffer.get_id() == buffer.get_id())
+            .map(|shared| shared.offset)
+    }
+
+    /// The total size, or an error if it exceeds the limit or the graph is
+    /// not acyclic.
+    pub fn check(&self) -> ThrillerResult<usize> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        if self.total > self.limit {
+            return Err(ThrillerError::SharedMemoryExceeded(self.total, self.limit));
+        }
+        Ok(self.total)
+    }
+
+    fn add_buffer(&mut self, buffer: &Rc<Buffer>, stages: usize, step: usize) {
+        if *buffer.get_typing() != BufType::SharedTile {
+            return;
+        }
+
+        match self
+            .buffers
+            .iter_mut()
+            .find(|shared| shared.buffer.get_id() == buffer.get_id())
+        {
+            Some(shared) => {
+                shared.stages = shared.stages.max(stages);
+                shared.last = shared.last.max(step);
+            }
+            None => self.buffers.push(SharedBuffer {
+                buffer: buffer.clone(),
+                stages,
+                first: step,
+                last: step,
+                offset: 0,
+            }),
+        }
+    }
+
+    fn add_edges(&mut self, edges: &[Rc<AttachedEdge>], block: &ThrillerBlock, step: usize) {
+        let pipelined = block.get_pipelined_buffers();
+        for edge in edges {
+            for buffer in [&edge.src, &edge.dst] {
+                let stages = if pipelined.iter().any(|buf| buf.get_id() == buffer.get_id()) {
+                    block.get_stages()
+                } else {
+                    1
+                };
+                self.add_buffer(buffer, stages, step);
+            }
+        }
+    }
+
+    /// Record the shared buffers accessed by `node`, starting at task `step`
+    /// and leaving `step` at the last task of `node`.
+    fn add_node(&mut self, node: &NodeRef, step: &mut usize) {
+        match node.borrow().get_inner() {
+            ThrillerNodeInner::Buffer(buffer) => self.add_buffer(buffer, 1, *step),
+            ThrillerNodeInner::Block(block) => self.add_block(block, step),
+            ThrillerNodeInner::Op(_) => {}
+        }
+    }
+
+    fn add_block(&mut self, block: &ThrillerBlock, step: &mut usize) {
+        let first = *step;
+        self.add_edges(&block.inputs, block, first);
+
+        let nodes = match block.subgraph.borrow().topo_sort() {
+            Ok(nodes) => nodes,
+            Err(error) => {
+                self.error = Some(error);
+                return;
+            }
+        };
+        for node in nodes.iter() {
+            *step += 1;
+            self.add_node(node, step);
+        }
+
+        *step += 1;
+        self.add_edges(&block.outputs, block, *step);
+
+        if block.ivars.is_empty() {
+            return;
+        }
+
+        // Buffers carried from one iteration to the next are live during
+        // the whole loop.
+        let pipelined = block.get_pipelined_buffers();
+        for shared in self.buffers.iter_mut() {
+            let carried = shared.first < first && shared.last >= first;
+            let prefetched = pipelined
+                .iter()
+                .any(|buf| buf.get_id() == shared.buffer.get_id());
+            if carried || prefetched {
+                shared.last = shared.last.max(*step);
+            }
+        }
+    }
+
+    /// Place every buffer at the lowest offset not used by the buffers
+    /// already placed with an overlapping lifetime.
+    fn assign_offsets(&mut self) {
+        let mut order = (0..self.buffers.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&index| std::cmp::Reverse(self.buffers[index].size()));
+
+        let mut placed: Vec<usize> = vec![];
+        for index in order {
+            let buffer = &self.buffers[index];
+            let mut used = placed
+                .iter()
+                .map(|&other| &self.buffers[other])
+                .filter(|other| other.overlaps(buffer))
+                .map(|other| (other.offset, other.offset + other.size()))
+                .collect::<Vec<_>>();
+            used.sort();
+
+            let mut offset = 0;
+            for (start, end) in used {
+                if offset + buffer.size() <= start {
+                    break;
+                }
+                offset = offset.max(end);
+            }
+
+            self.buffers[index].offset = offset;
+            placed.push(index);
+        }
+
+        self.total = self
+            .buffers
+            .iter()
+            .map(|buffer| buffer.offset + buffer.size())
+            .max()
+            .unwrap_or(0);
+    }
+}
+
+impl GraphPass for SharedMemoryPlanner {
+    fn run(&mut self, graph: &mut ThrillerGraph) {
+        let nodes = match graph.topo_sort() {
+            Ok(nodes) => nodes,
+            Err(error) => {
+                self.error = Some(error);
+                return;
+            }
+        };
+
+        let mut step = 0;
+        for node in nodes.iter() {
+            self.add_node(node, &mut step);
+            step += 1;
+        }
+        if self.error.is_some() {
+            return;
+        }
+
+        self.assign_offsets();
+
+        for buffer in self.buffers.iter() {
+            self.code += format!(
+                "auto* {name}_ptr = reinterpret_cast<{dtype}*>(shm + {offset});\n",
+                name = buffer.buffer.get_name(),
+                dtype = buffer.buffer.get_dtype(),
+                offset = buffer.offset
+            )
+            .as_str();
+        }
+
+        if self.total > self.limit {
+            error!(
+                "Shared memory of {} bytes exceeds the limit of {} bytes.",
+                self.total, self.limit
+            );
+        }
+    }
+}
@@ -3,6 +3,9 @@ use std::{fmt::Display, str::FromStr};
 use crate::ThrillerError;
 
 /// Data Type Define for NVIDIA GPU.
+///
+/// Buffers default to [`DataType::Cutlasshalf`] elements.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub enum DataType {
     /// 32-bit floating point.
     Float32,
@@ -11,9 +14,72 @@ pub enum DataType {
     /// 16-bit floating point.
     Half,
     /// Cutlass 16-bit floating point.
+    #[default]
     Cutlasshalf,
     /// Brain floating point.
     BF16,
+    /// TensorFloat-32, a 32-bit float with the precision of [`DataType::Half`].
+    TF32,
+    /// 8-bit floating point with 4 exponent and 3 mantissa bits.
+    Float8E4M3,
+    /// 8-bit floating point with 5 exponent and 2 mantissa bits.
+    Float8E5M2,
+    /// 8-bit signed integer.
+    Int8,
+    /// 32-bit signed integer.
+    Int32,
+}
+
+impl DataType {
+    /// Size of one element in bytes.
+    pub fn size_in_bytes(&self) -> usize {
+        match self {
+            DataType::Float64 => 8,
+            DataType::Float32 | DataType::TF32 | DataType::Int32 => 4,
+            DataType::Half | DataType::Cutlasshalf | DataType::BF16 => 2,
+            DataType::Float8E4M3 | DataType::Float8E5M2 | DataType::Int8 => 1,
+        }
+    }
+
+    /// The type GEMM products of this type are accumulated in by default,
+    /// e.g. `int8` products are accumulated in `int32`.
+    pub fn accumulator(&self) -> DataType {
+        match self {
+            DataType::Float64 => DataType::Float64,
+            DataType::Int8 | DataType::Int32 => DataType::Int32,
+            _ => DataType::Float32,
+        }
+    }
+
+    /// A short name that is a valid C++ identifier, used to name helpers
+    /// specialized for the type, e.g. `cast_f32_to_f16`. Use the
+    /// [`Display`] name to declare values of the type.
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            DataType::Float32 => "f32",
+            DataType::Float64 => "f64",
+            DataType::Half | DataType::Cutlasshalf => "f16",
+            DataType::BF16 => "bf16",
+            DataType::TF32 => "tf32",
+            DataType::Float8E4M3 => "f8e4m3",
+            DataType::Float8E5M2 => "f8e5m2",
+            DataType::Int8 => "i8",
+            DataType::Int32 => "i32",
+        }
+    }
+
+    /// Whether GEMM products of this type can be accumulated in `acc`.
+    ///
+    /// 16-bit and wider types may also accumulate in their own type, 8-bit
+    /// types only in their [`DataType::accumulator`].
+    pub fn can_accumulate_in(&self, acc: DataType) -> bool {
+        match self {
+            DataType::Float8E4M3 | DataType::Float8E5M2 | DataType::Int8 => {
+                acc == self.accumulator()
+            }
+            _ => acc == *self || acc == self.accumulator(),
+        }
+    }
 }
 
 impl FromStr for DataType {
@@ -25,6 +91,12 @@ impl FromStr for DataType {
             "double" => Ok(DataType::Float64),
             "half" => Ok(DataType::Half),
             "cutlass::half_t" => Ok(DataType::Cutlasshalf),
+            "cutlass::bfloat16_t" | "bfloat16" => Ok(DataType::BF16),
+            "cutlass::tfloat32_t" => Ok(DataType::TF32),
+            "cutlass::float_e4m3_t" => Ok(DataType::Float8E4M3),
+            "cutlass::float_e5m2_t" => Ok(DataType::Float8E5M2),
+            "int8_t" => Ok(DataType::Int8),
+            "int32_t" => Ok(DataType::Int32),
             _ => Err(ThrillerError::ParseError),
         }
     }
@@ -37,7 +109,12 @@ impl Display for DataType {
             DataType::Float64 => write!(f, "double"),
             DataType::Half => write!(f, "half"),
             DataType::Cutlasshalf => write!(f, "cutlass::half_t"),
-            DataType::BF16 => write!(f, "bfloat16"),
+            DataType::BF16 => write!(f, "cutlass::bfloat16_t"),
+            DataType::TF32 => write!(f, "cutlass::tfloat32_t"),
+            DataType::Float8E4M3 => write!(f, "cutlass::float_e4m3_t"),
+            DataType::Float8E5M2 => write!(f, "cutlass::float_e5m2_t"),
+            DataType::Int8 => write!(f, "int8_t"),
+            DataType::Int32 => write!(f, "int32_t"),
         }
     }
 }
@@ -1,3 +1,4 @@
+use std::cell::RefCell;
 use std::env::current_dir;
 use std::fs::File;
 use std::io::Write;
@@ -5,14 +6,21 @@ use std::path::Path;
 use std::process::Command;
 use std::rc::Rc;
 
+use crate::kernels::copy::Copy as CopyKernel;
 use crate::kernels::memory::Memory;
 
-use crate::{Buffer, RegularVar, Task, ThrillerBlock, ThrillerError, ThrillerResult, Var};
+use crate::{
+    Buffer, GraphPass, RegularVar, SharedMemoryPlanner, Task, ThrillerBlock, ThrillerError,
+    ThrillerGraph, ThrillerNode, ThrillerNodeInner, ThrillerResult, Var,
+};
 
 mod layout;
 
 pub use layout::{BlockLayout, BlockShape};
 
+/// Dynamic shared memory in bytes a kernel may use without opting in.
+const DEFAULT_SHARED_MEMORY_LIMIT: usize = 48 * 1024;
+
 /// `ThrillerEngine` is the main entry point for the ThrillerFlow framework.
 pub struct ThrillerEngine {
     dataflow_block: ThrillerBlock,
@@ -20,6 +28,7 @@ pub struct ThrillerEngine {
     outputs: Vec<(Rc<RegularVar>, Rc<Buffer>)>,
     input_blocks: Vec<Rc<BlockLayout>>,
     output_blocks: Vec<Rc<BlockLayout>>,
+    shared_memory_limit: usize,
 }
 
 impl ThrillerEngine {
@@ -31,6 +40,7 @@ impl ThrillerEngine {
             outputs: vec![],
             input_blocks: vec![],
             output_blocks: vec![],
+            shared_memory_limit: DEFAULT_SHARED_MEMORY_LIMIT,
         }
     }
 
@@ -54,23 +64,66 @@ impl ThrillerEngine {
         self.output_blocks.extend(output_blocks);
     }
 
+    /// Set the dynamic shared memory in bytes the kernel may use, 48 KiB by
+    /// default.
+    pub fn set_shared_memory_limit(&mut self, limit: usize) {
+        self.shared_memory_limit = limit;
+    }
+
+    /// Place the shared tiles of the dataflow block into the dynamic shared
+    /// memory buffer.
+    fn plan_shared_memory(&self) -> ThrillerResult<SharedMemoryPlanner> {
+        let mut graph = ThrillerGraph::new();
+        graph.add_nodes(vec![Rc::new(RefCell::new(ThrillerNode::new(
+            ThrillerNodeInner::Block(Rc::new(self.dataflow_block.clone())),
+        )))]);
+
+        let mut planner = SharedMemoryPlanner::new(self.shared_memory_limit);
+        planner.run(&mut graph);
+        planner.check()?;
+
+        Ok(planner)
+    }
+
+    /// Dynamic shared memory in bytes to launch the kernel with.
+    pub fn shared_memory_size(&self) -> ThrillerResult<usize> {
+        Ok(self.plan_shared_memory()?.total())
+    }
+
+    /// Emit the host side launch configuration of the kernel `sig`.
+    pub(crate) fn emit_launch_config<T: AsRef<str>>(&self, sig: T) -> ThrillerResult<String> {
+        Ok(format!(
+            "// Dynamic shared memory in bytes to launch `{sig}` with.\n\
+             constexpr int {sig}_shared_memory_size = {size};\n",
+            sig = sig.as_ref(),
+            size = self.shared_memory_size()?
+        ))
+    }
+
     /// Emit the function signature for the given dataflow block.
     pub(crate) fn emit_function_signature<T: AsRef<str>>(&self, sig: T) -> ThrillerResult<String> {
         let mut code = String::new();
         code += "template<typename Element, typename KeTraits>\n";
         code += format!("__global__ void {}(", sig.as_ref()).as_str();
         // TODO: Add function arguments.
-        for (index, (var, _)) in self.inputs.iter().enumerate() {
-            if index != 0 {
-                code += format!(", const Element* {}", var.get_name()).as_str();
-            } else {
-                code += format!("const Element* {}", var.get_name()).as_str();
-            }
+        let mut params = vec![];
+        for (var, buf) in self.inputs.iter() {
+            params.push(format!("const {}* {}", buf.get_dtype(), var.get_name()));
+        }
+
+        for (output, buf) in &self.outputs {
+            params.push(format!("{}* {}", buf.get_dtype(), output.get_name()));
         }
 
-        for (output, _) in &self.outputs {
-            code += format!(", Element* {}", output.get_name()).as_str();
+        // Symbolic loop bounds are passed as runtime kernel parameters.
+        let mut symbolic_vars = vec![];
+        self.dataflow_block
+            .collect_symbolic_vars(&mut symbolic_vars)?;
+        for var in symbolic_vars.iter() {
+            params.push(format!("int {}", var));
         }
+
+        code += params.join(", ").as_str();
         code += ")";
 
         Ok(code)
@@ -105,6 +158,7 @@ impl ThrillerEngine {
         code += "{\n";
         code += "// Declare shared memory buffer\n";
         code += Memory::emit_shared_buf_decl().as_str();
+        code += self.plan_shared_memory()?.code().as_str();
         code += "\n";
 
         // Add block layouts mappings
@@ -113,12 +167,13 @@ impl ThrillerEngine {
 
         for ((var, buf), input_block) in self.inputs.iter().zip(self.input_blocks.iter()) {
             code += format!(
-                "Element* {} = const_cast<Element*>({}) + blockIdx.x * {} + blockIdx.y * {} + blockIdx.z * {};\n",
+                "{dtype}* {} = const_cast<{dtype}*>({}) + blockIdx.x * {} + blockIdx.y * {} + blockIdx.z * {};\n",
                 buf.get_name(),
                 var.get_name(),
                 input_block.get_dim_x(),
                 input_block.get_dim_y(),
-                input_block.get_dim_z()
+                input_block.get_dim_z(),
+                dtype = buf.get_dtype()
             )
             .as_str();
         }
@@ -126,12 +181,13 @@ impl ThrillerEngine {
         for ((var, buf), output_block) in self.outputs.iter().zip(self.input_blocks.iter()) {
             // code += format!("auto {} = {};", input.get_name(), input_block.get_name()).as_str();
             code += format!(
-                "Element* {} = const_cast<Element*>({}) + blockIdx.x * {} + blockIdx.y * {} + blockIdx.z * {};\n",
+                "{dtype}* {} = const_cast<{dtype}*>({}) + blockIdx.x * {} + blockIdx.y * {} + blockIdx.z * {};\n",
                 buf.get_name(),
                 var.get_name(),
                 output_block.get_dim_x(),
                 output_block.get_dim_y(),
-                output_block.get_dim_z()
+                output_block.get_dim_z(),
+                dtype = buf.get_dtype()
             )
             .as_str();
         }
@@ -150,6 +206,10 @@ impl ThrillerEngine {
     pub fn persist<T: AsRef<str>>(&self, file_name: T, sig: T) -> ThrillerResult<()> {
         let mut code = self.emit_header()?;
         code += "namespace tiledcuda::kernels {\n\n";
+        code += CopyKernel::emit_r2r_decl().as_str();
+        code += "\n";
+        code += self.emit_launch_config(sig.as_ref())?.as_str();
+        code += "\n";
         code += self.emit_dataflow(sig)?.as_str();
         code += "\n}  // namespace tiledcuda::kernels\n";
 
@@ -1,5 +1,5 @@
 /// Errors that can occur in the thriller crate.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum ThrillerError {
     /// The given access pattern is invalid.
     InvalidAccessPattern,
@@ -15,6 +15,22 @@ pub enum ThrillerError {
     FailedFileOp,
     /// Failed to parse the gieven string.
     ParseError,
+    /// The given strides do not match or overlap the dimensions.
+    InvalidStrides,
+    /// The graph contains a cycle, given by the names of the nodes on it.
+    GraphCycle(Vec<String>),
+    /// The shapes of the operands do not match.
+    ShapeMismatch,
+    /// The buffer type of an operand is not supported by the task.
+    InvalidBufType,
+    /// The element types of the operands do not match.
+    DataTypeMismatch,
+    /// The planned shared memory exceeds the limit, both given in bytes.
+    SharedMemoryExceeded(usize, usize),
+    /// The estimated registers per thread exceed the limit.
+    RegisterPressureExceeded(usize, usize),
+    /// A symbolic loop bound is not an arithmetic expression of variables.
+    InvalidLoopBound(String),
 }
 
 /// Result type for thriller crate functions.
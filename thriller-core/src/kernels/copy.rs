@@ -1,4 +1,3 @@
-#[allow(dead_code)]
 /// Copy Primitive.
 pub struct Copy;
 
@@ -19,4 +18,26 @@ impl Copy {
             dst_index = dst_index
         )
     }
+
+    /// Emit the register to register copies used by [`crate::TileCopy`],
+    /// copying the elements of a register tile or vector into another one
+    /// with the same layout.
+    pub fn emit_r2r_decl() -> String {
+        let mut code = String::new();
+        code += "template <typename Src, typename Dst>\n";
+        code += "__device__ __forceinline__ void copy_tile_r2r(const Src& src, Dst& dst) {\n";
+        code +=
+            "    static_assert(Src::kNumel == Dst::kNumel, \"Register tiles differ in size.\");\n";
+        code += "#pragma unroll\n";
+        code += "    for (int i = 0; i < Src::kNumel; ++i) {\n";
+        code += "        dst.mutable_data()[i] = src.data()[i];\n";
+        code += "    }\n";
+        code += "}\n\n";
+        code += "template <typename Src, typename Dst>\n";
+        code += "__device__ __forceinline__ void copy_vec_r2r(const Src& src, Dst& dst) {\n";
+        code += "    copy_tile_r2r(src, dst);\n";
+        code += "}\n";
+
+        code
+    }
 }
@@ -1,3 +1,36 @@
-#[allow(dead_code)]
+use crate::Layout as ShapeLayout;
+use crate::{Dimension, Shape};
+
 /// Layout Primitives.
 pub struct Layout;
+
+impl Layout {
+    /// Emit a CuTe layout type with the dims and strides of the given shape,
+    /// composed with its swizzle function if any.
+    pub fn emit_layout(shape: &Shape) -> String {
+        let join = |values: &[usize]| {
+            values
+                .iter()
+                .map(|value| format!("cute::Int<{}>", value))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let layout = format!(
+            "cute::Layout<cute::Shape<{dims}>, cute::Stride<{strides}>>",
+            dims = join(shape.get_dims().slice()),
+            strides = join(shape.get_strides().slice())
+        );
+
+        match shape.get_layout() {
+            ShapeLayout::Swizzle(swizzle) => format!(
+                "cute::ComposedLayout<cute::Swizzle<{bits}, {base}, {shift}>, cute::_0, {layout}>",
+                bits = swizzle.bits,
+                base = swizzle.base,
+                shift = swizzle.shift,
+                layout = layout
+            ),
+            _ => layout,
+        }
+    }
+}
@@ -6,7 +6,8 @@ impl Memory {
     pub fn emit_shared_buf_decl() -> String {
         let mut code = String::new();
         code += "extern __shared__ __align__(sizeof(double)) unsigned char shared_buf[];\n";
-        code += "auto* shm = reinterpret_cast<Element*>(shared_buf);\n";
+        // Shared buffers are placed at byte offsets, see `SharedMemoryPlanner`.
+        code += "unsigned char* shm = shared_buf;\n";
 
         code
     }
@@ -1,14 +1,37 @@
 /// Synchronization primitives.
-pub struct Sync;
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sync {
+    /// `cp.async.commit_group`, commits the async copies issued so far as
+    /// one group.
+    CommitGroup,
+    /// `cp.async.wait_group<N>`, waits until at most `N` groups of async
+    /// copies are in flight.
+    WaitGroup(usize),
+    /// `__syncthreads()`, a barrier for all threads of the thread block.
+    Threads,
+    /// `__syncwarp()`, a barrier for the threads of a warp.
+    Warp,
+    /// `bar.sync`, a named barrier for a subset of the thread block.
+    NamedBarrier {
+        /// The barrier id, 0 being used by `__syncthreads()`.
+        id: usize,
+        /// The number of participating threads, a multiple of the warp size.
+        threads: usize,
+    },
+}
 
 impl Sync {
-    /// Emit a synchronization primitive.
-    pub fn emit_sync() -> String {
-        "__syncthreads();\n".to_string()
-    }
-
-    /// Emit a copy async primitive.
-    pub fn emit_copy_async() -> String {
-        "__copy_async();\n".to_string()
+    /// Emit the synchronization primitive.
+    pub fn emit(&self) -> String {
+        match self {
+            Sync::CommitGroup => "cute::cp_async_fence();\n".to_string(),
+            Sync::WaitGroup(pending) => format!("cute::cp_async_wait<{}>();\n", pending),
+            Sync::Threads => "__syncthreads();\n".to_string(),
+            Sync::Warp => "__syncwarp();\n".to_string(),
+            Sync::NamedBarrier { id, threads } => format!(
+                "asm volatile(\"bar.sync %0, %1;\" :: \"r\"({}), \"r\"({}));\n",
+                id, threads
+            ),
+        }
     }
 }
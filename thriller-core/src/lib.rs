@@ -49,15 +49,21 @@ mod var;
 pub use access::{AccessMap, AccessMatrix, AccessOffset};
 pub use buffer::{BufType, Buffer};
 pub use dataflow::{
-    AllocateEdge, AllocateVar, AttachedEdge, GraphPass, ThrillerBlock, ThrillerEdge, ThrillerGraph,
+    AccessKind, AllocateEdge, AllocateVar, AttachedEdge, BarrierElimination, Dependence,
+    DependenceAnalysis, DependenceKind, Diagnostic, Distance, GraphPass, LoopFusion,
+    LoopInterchange, LoopTiling, MemoryAccess, ReductionAnalysis, RegisterPressure,
+    ShapeValidation, SharedMemoryPlanner, ThreadScope, ThrillerBlock, ThrillerEdge, ThrillerGraph,
     ThrillerNode, ThrillerNodeInner,
 };
 pub use dtype::DataType;
 pub use engine::{BlockLayout, BlockShape, ThrillerEngine};
 pub use error::{ThrillerError, ThrillerResult};
 pub use log::{debug, error, info, init_logger, set_max_level, trace, warn};
-pub use shape::{Dim, Dimension, Layout, Shape};
-pub use task::{Convert, Gemm, Task};
+pub use shape::{Dim, Dimension, Layout, Shape, Swizzle};
+pub use task::{
+    BatchedGemm, BinaryOp, Broadcast, Combiner, Convert, Gemm, GroupedGemm, Map, MapOp, Reduce,
+    Task, TileCopy, UnaryOp,
+};
 pub use var::{IterationBound, IterationVar, RegularVar, Var};
 
 use id::ID_COUNTER;
@@ -1,5 +1,7 @@
 use smallvec::{smallvec, SmallVec};
 
+use crate::{DataType, ThrillerError, ThrillerResult};
+
 /// Array index type.
 pub type Ix = usize;
 
@@ -33,6 +35,31 @@ pub enum Layout<D> {
     ColumnMajor,
     /// Custom strides
     Custom(D),
+    /// Row-major with the offsets permuted by a [`Swizzle`] function,
+    /// used by shared memory tiles to avoid bank conflicts.
+    Swizzle(Swizzle),
+}
+
+/// Swizzle function parameterised like CuTe's `Swizzle<B, M, S>`.
+///
+/// The `bits` bits starting at bit `base + shift` of an element offset are
+/// XOR-ed into the `bits` bits starting at bit `base`, which permutes
+/// the units of `2^base` elements within each row of a tile.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Swizzle {
+    /// Number of bits in the mask (`B`).
+    pub bits: usize,
+    /// Number of least significant bits kept constant (`M`).
+    pub base: usize,
+    /// Distance to shift the mask (`S`).
+    pub shift: usize,
+}
+
+impl Swizzle {
+    /// Create a new Swizzle function.
+    pub fn new(bits: usize, base: usize, shift: usize) -> Self {
+        Swizzle { bits, base, shift }
+    }
 }
 
 impl<D> Layout<D>
@@ -41,11 +68,9 @@ where
 {
     pub(crate) fn strides_for_dim(self, dim: &D) -> D {
         match self {
-            Layout::RowMajor => dim.default_strides(),
+            Layout::RowMajor | Layout::Swizzle(_) => dim.default_strides(),
             Layout::ColumnMajor => dim.fortran_strides(),
-            Layout::Custom(_) => {
-                todo!()
-            }
+            Layout::Custom(strides) => strides,
         }
     }
 }
@@ -148,6 +173,9 @@ pub struct Shape {
 
 impl Shape {
     /// Create a new Shape.
+    ///
+    /// Strides given through [`Layout::Custom`] are taken as is, use
+    /// [`Shape::with_strides`] to have them validated against the dims.
     pub fn new(dims: &[Ix], layout: Layout<Dim>) -> Self {
         Self {
             dims: Dim::new(dims),
@@ -155,8 +183,78 @@ impl Shape {
         }
     }
 
+    /// Create a new Shape with explicit strides, e.g. for padded or sliced tiles.
+    ///
+    /// The strides must have one entry per dimension and must not map two
+    /// different indices to the same element.
+    pub fn with_strides(dims: &[Ix], strides: &[Ix]) -> ThrillerResult<Self> {
+        if dims.len() != strides.len() {
+            return Err(ThrillerError::InvalidStrides);
+        }
+
+        // Sort the axes by stride, each axis must step over the whole
+        // extent of the previous one.
+        let mut axes = dims
+            .iter()
+            .zip(strides.iter())
+            .filter(|(&dim, _)| dim > 1)
+            .collect::<Vec<_>>();
+        axes.sort_by_key(|(_, &stride)| stride);
+
+        let mut extent = 1;
+        for (&dim, &stride) in axes {
+            if stride < extent {
+                return Err(ThrillerError::InvalidStrides);
+            }
+            extent = stride * dim;
+        }
+
+        Ok(Self::new(dims, Layout::Custom(Dim::new(strides))))
+    }
+
+    /// Get the dimensions.
+    pub fn get_dims(&self) -> &Dim {
+        &self.dims
+    }
+
+    /// Get the layout.
+    pub fn get_layout(&self) -> &Layout<Dim> {
+        &self.layout
+    }
+
     /// Compute the stride for the given dimension.
     pub fn get_strides(&self) -> Dim {
         self.layout.clone().strides_for_dim(&self.dims)
     }
+
+    /// Number of elements addressed by the shape.
+    pub fn num_elements(&self) -> usize {
+        self.dims.slice().iter().product()
+    }
+
+    /// Number of elements spanned in memory, including padding.
+    fn num_spanned_elements(&self) -> usize {
+        if self.num_elements() == 0 {
+            return 0;
+        }
+
+        let strides = self.get_strides();
+        self.dims
+            .slice()
+            .iter()
+            .zip(strides.slice().iter())
+            .map(|(dim, stride)| (dim - 1) * stride)
+            .sum::<usize>()
+            + 1
+    }
+
+    /// Size in bytes of the memory spanned by the shape for the given data type.
+    pub fn size_in_bytes(&self, dtype: DataType) -> usize {
+        self.num_spanned_elements() * dtype.size_in_bytes()
+    }
+
+    /// Whether the elements are laid out without gaps.
+    pub fn is_contiguous(&self) -> bool {
+        self.num_spanned_elements() == self.num_elements()
+    }
 }
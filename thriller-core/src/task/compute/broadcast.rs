@@ -0,0 +1,76 @@
+use std::rc::Rc;
+
+use crate::{next_id, BinaryOp, BufType, Buffer, Dimension, Task, ThrillerError, ThrillerResult};
+
+/// [`Broadcast`] is a task that combines a 2-dimensional register tile with
+/// a register vector repeated along one axis, e.g. subtracting the row
+/// maximum or dividing by the row sum in softmax.
+///
+/// Along axis 1 the vector holds one element per row of the tile, as
+/// produced by a [`crate::Reduce`] along the same axis, along axis 0 one
+/// element per column.
+pub struct Broadcast {
+    op: BinaryOp,
+    tile: Rc<Buffer>,
+    vec: Rc<Buffer>,
+    dst: Rc<Buffer>,
+    axis: usize,
+    id: usize,
+}
+
+impl Broadcast {
+    /// Create a new `Broadcast` task computing `dst = op(tile, vec)`.
+    pub fn new(
+        op: BinaryOp,
+        tile: Rc<Buffer>,
+        vec: Rc<Buffer>,
+        dst: Rc<Buffer>,
+        axis: usize,
+    ) -> ThrillerResult<Self> {
+        if *tile.get_typing() != BufType::RegTile
+            || *dst.get_typing() != BufType::RegTile
+            || *vec.get_typing() != BufType::RegVec
+        {
+            return Err(ThrillerError::InvalidBufType);
+        }
+
+        let tile_dims = tile.get_shape().get_dims().slice();
+        if tile_dims.len() != 2 || axis > 1 || dst.get_shape().get_dims().slice() != tile_dims {
+            return Err(ThrillerError::ShapeMismatch);
+        }
+
+        if vec.get_shape().get_dims().slice() != [tile_dims[1 - axis]] {
+            return Err(ThrillerError::ShapeMismatch);
+        }
+
+        if vec.get_dtype() != tile.get_dtype() || dst.get_dtype() != tile.get_dtype() {
+            return Err(ThrillerError::DataTypeMismatch);
+        }
+
+        Ok(Broadcast {
+            op,
+            tile,
+            vec,
+            dst,
+            axis,
+            id: next_id(),
+        })
+    }
+}
+
+impl Task for Broadcast {
+    fn emit(&self) -> ThrillerResult<String> {
+        Ok(format!(
+            "compute::broadcast_{direction}_{op}({tile}, {vec}, {dst});\n",
+            direction = if self.axis == 1 { "row" } else { "col" },
+            op = self.op,
+            tile = self.tile.get_name(),
+            vec = self.vec.get_name(),
+            dst = self.dst.get_name()
+        ))
+    }
+
+    fn get_name(&self) -> String {
+        format!("Broadcast_{}", self.id)
+    }
+}
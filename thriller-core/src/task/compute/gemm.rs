@@ -1,19 +1,30 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use crate::{next_id, AccessMap, Task, ThrillerError, ThrillerNode, ThrillerResult, Var};
+use crate::{
+    next_id, AccessKind, AccessMap, AccessMatrix, AccessOffset, BufType, Buffer, Diagnostic,
+    Dimension, IterationBound, IterationVar, MemoryAccess, Task, ThrillerError, ThrillerNode,
+    ThrillerNodeInner, ThrillerResult, Var,
+};
 
 /// [`Gemm`] is a task that computes the General Matrix-Matrix Multiplication
 /// operation in register level.
+///
+/// The [`AccessMap`] is either empty, or holds one access matrix for each of
+/// the operands `A`, `B` and `C`, with an optional offset each, indexing the
+/// operands with the iteration variables of the enclosing loops.
 pub struct Gemm {
     prevs: Vec<Rc<RefCell<ThrillerNode>>>,
     next: Rc<RefCell<ThrillerNode>>,
     access_map: Rc<AccessMap>,
+    transpose_a: bool,
+    transpose_b: bool,
+    accumulate: bool,
     id: usize,
 }
 
 impl Gemm {
-    /// Create a new GEMM task.
+    /// Create a new GEMM task computing `C += A * B`.
     pub fn new(
         prevs: Vec<Rc<RefCell<ThrillerNode>>>,
         next: Rc<RefCell<ThrillerNode>>,
@@ -23,9 +34,71 @@ impl Gemm {
             prevs,
             next,
             access_map,
+            transpose_a: false,
+            transpose_b: false,
+            accumulate: true,
             id: next_id(),
         }
     }
+
+    /// Set whether `A` and `B` are transposed.
+    pub fn set_transpose(&mut self, transpose_a: bool, transpose_b: bool) {
+        self.transpose_a = transpose_a;
+        self.transpose_b = transpose_b;
+    }
+
+    /// Set whether the product is accumulated into `C` or overwrites it.
+    pub fn set_accumulate(&mut self, accumulate: bool) {
+        self.accumulate = accumulate;
+    }
+
+    /// Emit the index of each operand, e.g. `[1 * i + 0][1 * j + 0]`.
+    fn emit_access(&self) -> ThrillerResult<Vec<String>> {
+        let access_map = &self.access_map;
+        let access_matrixs = access_map.get_access_matrixs();
+        let access_offsets = access_map.get_access_offsets();
+        let iter_vars = access_map.get_iter_vars();
+
+        // (A, B, C)
+        if access_matrixs.is_empty() {
+            return Ok(vec![String::new(); 3]);
+        }
+        if access_matrixs.len() != 3 || access_offsets.len() > 3 {
+            return Err(ThrillerError::InvalidAccessPattern);
+        }
+
+        let mut access_codes = vec![];
+        for (i, matrix) in access_matrixs.iter().enumerate() {
+            let offsets = access_offsets.get(i).map(|offset| &offset.0);
+            if offsets.is_some_and(|offsets| offsets.len() > matrix.0.len()) {
+                return Err(ThrillerError::InvalidAccessPattern);
+            }
+
+            let mut code = String::new();
+            // An operand without any access is not indexed.
+            let indexed = matrix.0.iter().flatten().any(|&access| access != 0)
+                || offsets.is_some_and(|offsets| offsets.iter().any(|&offset| offset != 0));
+
+            for (j, row) in matrix.0.iter().enumerate() {
+                if row.len() != iter_vars.len() {
+                    return Err(ThrillerError::InvalidAccessPattern);
+                }
+                if !indexed {
+                    continue;
+                }
+
+                let offset = offsets
+                    .and_then(|offsets| offsets.get(j))
+                    .copied()
+                    .unwrap_or(0);
+                code += emit_index(row, offset, iter_vars).as_str();
+            }
+
+            access_codes.push(code);
+        }
+
+        Ok(access_codes)
+    }
 }
 
 impl Task for Gemm {
@@ -34,55 +107,399 @@ impl Task for Gemm {
             return Err(ThrillerError::WrongInputsNum);
         }
 
-        let mut code = String::new();
+        let access_codes = self.emit_access()?;
 
-        let gemm = |access_map: &AccessMap| -> ThrillerResult<String> {
-            let mut code = String::new();
+        // Transposed operands and overwriting `C` are selected by template
+        // arguments, the plain call accumulates `A * B` into `C`.
+        let options = if self.transpose_a || self.transpose_b || !self.accumulate {
+            format!(
+                "<{}, {}, {}>",
+                self.transpose_a, self.transpose_b, self.accumulate
+            )
+        } else {
+            String::new()
+        };
 
-            let access_matrixs = access_map.get_access_matrixs();
-            let access_offsets = access_map.get_access_offsets();
-            let iter_vars = access_map.get_iter_vars();
-            // (A, B, C)
-            let mut access_codes = vec![String::new(); 3];
-
-            for (i, matrix) in access_matrixs.iter().enumerate() {
-                for (j, access) in matrix.0.iter().enumerate() {
-                    for (iter_index, &access_index) in access.iter().enumerate() {
-                        if access_index != 0 {
-                            let iter_var = iter_vars[iter_index].get_name();
-                            access_codes[i] += format!(
-                                "[{access} * {iter_var} + {offset}]",
-                                access = access_index,
-                                iter_var = iter_var,
-                                offset = access_offsets[0].0[j]
-                            )
-                            .as_str();
-                        }
-                    }
-                }
+        Ok(format!(
+            "compute::gemm_{options}({buf_a}{a}, {buf_b}{b}, {buf_c}{c});\n",
+            options = options,
+            a = access_codes[0],
+            b = access_codes[1],
+            c = access_codes[2],
+            buf_a = self.prevs[0].borrow().get_name(),
+            buf_b = self.prevs[1].borrow().get_name(),
+            buf_c = self.next.borrow().get_name()
+        ))
+    }
+
+    fn get_name(&self) -> String {
+        format!("Gemm_{}", self.id)
+    }
+
+    fn get_access_map(&self) -> Option<&Rc<AccessMap>> {
+        Some(&self.access_map)
+    }
+
+    fn set_access_map(&mut self, access_map: Rc<AccessMap>) {
+        self.access_map = access_map;
+    }
+
+    fn get_accesses(&self) -> Vec<MemoryAccess> {
+        let (Some(a), Some(b), Some(c)) = (
+            self.prevs.first().and_then(node_buffer),
+            self.prevs.get(1).and_then(node_buffer),
+            node_buffer(&self.next),
+        ) else {
+            return vec![];
+        };
+
+        let access = |buffer: Rc<Buffer>, index: usize, kind: AccessKind| {
+            MemoryAccess::new(buffer, self.access_map.clone(), index, kind)
+        };
+        let mut accesses = vec![
+            access(a, 0, AccessKind::Read),
+            access(b, 1, AccessKind::Read),
+        ];
+        // The accumulator is read before it is written.
+        if self.accumulate {
+            accesses.push(access(c.clone(), 2, AccessKind::Read));
+        }
+        accesses.push(access(c, 2, AccessKind::Write));
+        accesses
+    }
+
+    fn validate(&self) -> Vec<Diagnostic> {
+        let name = self.get_name();
+
+        if self.prevs.len() != 2 {
+            return vec![Diagnostic::new(
+                &name,
+                ThrillerError::WrongInputsNum,
+                format!("expects 2 inputs, got {}", self.prevs.len()),
+            )];
+        }
+        if let Err(error) = self.emit_access() {
+            return vec![Diagnostic::new(
+                &name,
+                error,
+                "access map does not index A, B and C".to_string(),
+            )];
+        }
+
+        let (Some(a), Some(b), Some(c)) = (
+            node_buffer(&self.prevs[0]),
+            node_buffer(&self.prevs[1]),
+            node_buffer(&self.next),
+        ) else {
+            return vec![];
+        };
+
+        // The leading dimensions are indexed by the access map, the GEMM
+        // runs on the trailing 2-dimensional tiles.
+        let tile = |buf: &Buffer, transpose: bool| {
+            let dims = buf.get_shape().get_dims().slice();
+            let [rows, cols] = dims[dims.len().checked_sub(2)?..] else {
+                return None;
+            };
+            Some(if transpose {
+                [cols, rows]
+            } else {
+                [rows, cols]
+            })
+        };
+
+        let mut diagnostics = vec![];
+        let (Some([m, k]), Some([kb, n]), Some([mc, nc])) = (
+            tile(&a, self.transpose_a),
+            tile(&b, self.transpose_b),
+            tile(&c, false),
+        ) else {
+            diagnostics.push(Diagnostic::new(
+                &name,
+                ThrillerError::ShapeMismatch,
+                format!(
+                    "operands {}, {} and {} must have at least 2 dimensions",
+                    a.get_name(),
+                    b.get_name(),
+                    c.get_name()
+                ),
+            ));
+            return diagnostics;
+        };
+
+        if a.get_dtype() != b.get_dtype() {
+            diagnostics.push(Diagnostic::new(
+                &name,
+                ThrillerError::DataTypeMismatch,
+                format!(
+                    "{}: {} and {}: {} have different element types",
+                    a.get_name(),
+                    a.get_dtype(),
+                    b.get_name(),
+                    b.get_dtype()
+                ),
+            ));
+        }
+
+        if !a.get_dtype().can_accumulate_in(c.get_dtype()) {
+            diagnostics.push(Diagnostic::new(
+                &name,
+                ThrillerError::DataTypeMismatch,
+                format!(
+                    "{} products cannot be accumulated in {}: {}",
+                    a.get_dtype(),
+                    c.get_name(),
+                    c.get_dtype()
+                ),
+            ));
+        }
+
+        if k != kb || m != mc || n != nc {
+            diagnostics.push(Diagnostic::new(
+                &name,
+                ThrillerError::ShapeMismatch,
+                format!(
+                    "{}: {}x{} * {}: {}x{} does not yield {}: {}x{}",
+                    a.get_name(),
+                    m,
+                    k,
+                    b.get_name(),
+                    kb,
+                    n,
+                    c.get_name(),
+                    mc,
+                    nc
+                ),
+            ));
+        }
+
+        diagnostics
+    }
+}
+
+/// Emit one index of an operand, e.g. `[1 * i + 0]`.
+fn emit_index(row: &[usize], offset: usize, iter_vars: &[Rc<IterationVar>]) -> String {
+    let terms = row
+        .iter()
+        .zip(iter_vars.iter())
+        .filter(|(&access, _)| access != 0)
+        .map(|(access, iter_var)| format!("{} * {}", access, iter_var.get_name()))
+        .collect::<Vec<_>>();
+
+    if terms.is_empty() {
+        format!("[{}]", offset)
+    } else {
+        format!("[{} + {}]", terms.join(" + "), offset)
+    }
+}
+
+/// The buffer held by a buffer node.
+fn node_buffer(node: &Rc<RefCell<ThrillerNode>>) -> Option<Rc<Buffer>> {
+    match node.borrow().get_inner() {
+        ThrillerNodeInner::Buffer(buf) => Some(buf.clone()),
+        _ => None,
+    }
+}
+
+/// Check that `A`, `B` and `C` are 3-dimensional register tiles of shapes
+/// `[batch, m, k]`, `[batch, k, n]` and `[batch, m, n]`, with `A` and `B`
+/// of the same element type that can be accumulated in the type of `C`,
+/// and return `[batch, m, n, k]`.
+fn check_batched_operands(a: &Buffer, b: &Buffer, c: &Buffer) -> ThrillerResult<[usize; 4]> {
+    if [a, b, c]
+        .iter()
+        .any(|buf| *buf.get_typing() != BufType::RegTile)
+    {
+        return Err(ThrillerError::InvalidBufType);
+    }
+
+    let (a_dims, b_dims, c_dims) = (
+        a.get_shape().get_dims().slice(),
+        b.get_shape().get_dims().slice(),
+        c.get_shape().get_dims().slice(),
+    );
+    if a_dims.len() != 3 || b_dims.len() != 3 || c_dims.len() != 3 {
+        return Err(ThrillerError::ShapeMismatch);
+    }
+
+    let [batch, m, k] = [a_dims[0], a_dims[1], a_dims[2]];
+    let n = b_dims[2];
+    if b_dims != [batch, k, n] || c_dims != [batch, m, n] {
+        return Err(ThrillerError::ShapeMismatch);
+    }
+
+    if a.get_dtype() != b.get_dtype() || !a.get_dtype().can_accumulate_in(c.get_dtype()) {
+        return Err(ThrillerError::DataTypeMismatch);
+    }
+
+    Ok([batch, m, n, k])
+}
+
+/// [`BatchedGemm`] is a task that computes `C[b] += A[b] * B[b]` in register
+/// level, where the leading dimension of each operand is indexed by the
+/// batch [`IterationVar`] `b`.
+///
+/// The batch index is held by an [`AccessMap`] with one access matrix and
+/// offset per operand, so that loop transformations rewriting it keep the
+/// index in sync with the enclosing loops.
+pub struct BatchedGemm {
+    a: Rc<Buffer>,
+    b: Rc<Buffer>,
+    c: Rc<Buffer>,
+    access_map: Rc<AccessMap>,
+    id: usize,
+}
+
+impl BatchedGemm {
+    /// Create a new batched GEMM task over the operands of shapes
+    /// `[batch, m, k]`, `[batch, k, n]` and `[batch, m, n]`.
+    ///
+    /// A fixed domain of the batch variable must not exceed the batch size.
+    pub fn new(
+        a: Rc<Buffer>,
+        b: Rc<Buffer>,
+        c: Rc<Buffer>,
+        batch: Rc<IterationVar>,
+    ) -> ThrillerResult<Self> {
+        let [size, ..] = check_batched_operands(&a, &b, &c)?;
+
+        if let (IterationBound::Fixed(lower), IterationBound::Fixed(upper)) = batch.get_domain() {
+            if upper < lower || *upper > size {
+                return Err(ThrillerError::ShapeMismatch);
             }
+        }
 
-            code += format!(
-                "compute::gemm_({buf_a}{a}, {buf_b}{b}, {buf_c}{c});\n",
-                a = access_codes[0],
-                b = access_codes[1],
-                c = access_codes[2],
-                buf_a = self.prevs[0].borrow().get_name(),
-                buf_b = self.prevs[1].borrow().get_name(),
-                buf_c = self.next.borrow().get_name()
-            )
-            .as_str();
+        // (A, B, C)[1 * b + 0]
+        let mut access_map = AccessMap::new(1, vec![1; 3]);
+        access_map.add_iter_var(batch);
+        access_map.add_access_matrixs(vec![AccessMatrix(vec![vec![1]]); 3]);
+        access_map.add_access_offsets(vec![AccessOffset(vec![0]); 3]);
+
+        Ok(BatchedGemm {
+            a,
+            b,
+            c,
+            access_map: Rc::new(access_map),
+            id: next_id(),
+        })
+    }
+}
+
+impl Task for BatchedGemm {
+    fn emit(&self) -> ThrillerResult<String> {
+        let access_matrixs = self.access_map.get_access_matrixs();
+        let access_offsets = self.access_map.get_access_offsets();
+        let iter_vars = self.access_map.get_iter_vars();
 
-            Ok(code)
+        // One row indexing the batch dimension of each operand.
+        let index = |operand: usize| match access_matrixs.get(operand).map(|m| &m.0[..]) {
+            Some([row]) if row.len() == iter_vars.len() => {
+                let offset = access_offsets
+                    .get(operand)
+                    .and_then(|offset| offset.0.first())
+                    .copied()
+                    .unwrap_or(0);
+                Ok(emit_index(row, offset, iter_vars))
+            }
+            _ => Err(ThrillerError::InvalidAccessPattern),
         };
 
-        let gemm_code = gemm(&self.access_map)?;
-        code += gemm_code.as_str();
+        Ok(format!(
+            "compute::gemm_({a}{ia}, {b}{ib}, {c}{ic});\n",
+            a = self.a.get_name(),
+            b = self.b.get_name(),
+            c = self.c.get_name(),
+            ia = index(0)?,
+            ib = index(1)?,
+            ic = index(2)?
+        ))
+    }
+
+    fn get_name(&self) -> String {
+        format!("BatchedGemm_{}", self.id)
+    }
+
+    fn get_access_map(&self) -> Option<&Rc<AccessMap>> {
+        Some(&self.access_map)
+    }
+
+    fn set_access_map(&mut self, access_map: Rc<AccessMap>) {
+        self.access_map = access_map;
+    }
+
+    fn get_accesses(&self) -> Vec<MemoryAccess> {
+        let access = |buffer: &Rc<Buffer>, index: usize, kind: AccessKind| {
+            MemoryAccess::new(buffer.clone(), self.access_map.clone(), index, kind)
+        };
+        vec![
+            access(&self.a, 0, AccessKind::Read),
+            access(&self.b, 1, AccessKind::Read),
+            access(&self.c, 2, AccessKind::Read),
+            access(&self.c, 2, AccessKind::Write),
+        ]
+    }
+}
+
+/// [`GroupedGemm`] is a task that computes `C[g] += A[g] * B[g]` in register
+/// level for every group `g`, where each group has its own `[m, n, k]` shape.
+///
+/// The operands have the shapes `[groups, m, k]`, `[groups, k, n]` and
+/// `[groups, m, n]` given by the largest group, smaller groups only use
+/// the leading part of their tiles.
+pub struct GroupedGemm {
+    a: Rc<Buffer>,
+    b: Rc<Buffer>,
+    c: Rc<Buffer>,
+    shapes: Vec<[usize; 3]>,
+    id: usize,
+}
+
+impl GroupedGemm {
+    /// Create a new grouped GEMM task, with the `[m, n, k]` shape of each
+    /// group in `shapes`.
+    pub fn new(
+        a: Rc<Buffer>,
+        b: Rc<Buffer>,
+        c: Rc<Buffer>,
+        shapes: Vec<[usize; 3]>,
+    ) -> ThrillerResult<Self> {
+        let [groups, m, n, k] = check_batched_operands(&a, &b, &c)?;
+
+        if shapes.len() != groups
+            || shapes
+                .iter()
+                .any(|&[gm, gn, gk]| gm == 0 || gn == 0 || gk == 0 || gm > m || gn > n || gk > k)
+        {
+            return Err(ThrillerError::ShapeMismatch);
+        }
+
+        Ok(GroupedGemm {
+            a,
+            b,
+            c,
+            shapes,
+            id: next_id(),
+        })
+    }
+}
+
+impl Task for GroupedGemm {
+    fn emit(&self) -> ThrillerResult<String> {
+        let mut code = String::new();
+        for (group, [m, n, k]) in self.shapes.iter().enumerate() {
+            code += format!(
+                "compute::grouped_gemm_<{m}, {n}, {k}>({a}[{group}], {b}[{group}], {c}[{group}]);\n",
+                a = self.a.get_name(),
+                b = self.b.get_name(),
+                c = self.c.get_name(),
+            )
+            .as_str();
+        }
 
         Ok(code)
     }
 
     fn get_name(&self) -> String {
-        format!("Gemm_{}", self.id)
+        format!("GroupedGemm_{}", self.id)
     }
 }
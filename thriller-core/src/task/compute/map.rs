@@ -1,39 +1,30 @@
+use std::fmt::Display;
 use std::rc::Rc;
 
+use crate::next_id;
+use crate::BufType;
 use crate::Buffer;
-use crate::DataType;
+use crate::Diagnostic;
 use crate::Task;
+use crate::ThrillerError;
 use crate::ThrillerResult;
 
 /// Convert a variable to a different type.
+///
+/// The source and destination types are the element types of the buffers.
 pub struct Convert {
     src_buf: Rc<Buffer>,
     dst_buf: Rc<Buffer>,
-    src_type: DataType,
-    dst_type: DataType,
+    id: usize,
 }
 
 impl Convert {
     /// Create a new `Convert` task.
-    pub fn new(
-        src_buf: Rc<Buffer>,
-        dst_buf: Rc<Buffer>,
-        src_type: DataType,
-        dst_type: DataType,
-    ) -> Self {
-        // `src_buf` and `dst_buf` must have the same typing.
-        // TODO(KuanjuX): Don't use assert here.
-        assert_eq!(src_buf.get_typing(), dst_buf.get_typing());
-
-        // `src_buf` and `dst_buf` must have the same shape.
-        // TODO(KuanjuX): Don't use assert here.
-        assert_eq!(src_buf.get_shape(), dst_buf.get_shape());
-
+    pub fn new(src_buf: Rc<Buffer>, dst_buf: Rc<Buffer>) -> Self {
         Self {
             src_buf,
             dst_buf,
-            src_type,
-            dst_type,
+            id: next_id(),
         }
     }
 }
@@ -42,14 +33,209 @@ impl Task for Convert {
     fn emit(&self) -> ThrillerResult<String> {
         Ok(format!(
             "cast_{src_type}_to_{dst_type}({src_buf}, {dst_buf});\n",
-            src_type = self.src_type,
-            dst_type = self.dst_type,
+            src_type = self.src_buf.get_dtype().short_name(),
+            dst_type = self.dst_buf.get_dtype().short_name(),
             src_buf = self.src_buf.get_name(),
             dst_buf = self.dst_buf.get_name(),
         ))
     }
 
     fn get_name(&self) -> String {
-        todo!()
+        format!("Convert_{}", self.id)
+    }
+
+    fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+
+        // `src_buf` and `dst_buf` must have the same typing.
+        if self.src_buf.get_typing() != self.dst_buf.get_typing() {
+            diagnostics.push(Diagnostic::new(
+                &self.get_name(),
+                ThrillerError::InvalidBufType,
+                format!(
+                    "cannot convert {:?} {} into {:?} {}",
+                    self.src_buf.get_typing(),
+                    self.src_buf.get_name(),
+                    self.dst_buf.get_typing(),
+                    self.dst_buf.get_name()
+                ),
+            ));
+        }
+
+        // `src_buf` and `dst_buf` must have the same shape.
+        if self.src_buf.get_shape() != self.dst_buf.get_shape() {
+            diagnostics.push(Diagnostic::new(
+                &self.get_name(),
+                ThrillerError::ShapeMismatch,
+                format!(
+                    "{} and {} have different shapes",
+                    self.src_buf.get_name(),
+                    self.dst_buf.get_name()
+                ),
+            ));
+        }
+
+        diagnostics
+    }
+}
+
+/// Unary elementwise operators.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnaryOp {
+    /// `exp(x)`.
+    Exp,
+    /// `log(x)`.
+    Log,
+    /// `-x`.
+    Neg,
+    /// `max(x, 0)`.
+    Relu,
+    /// Gaussian error linear unit.
+    Gelu,
+    /// `x * factor`.
+    Scale(f32),
+}
+
+/// Binary elementwise operators.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOp {
+    /// `x + y`.
+    Add,
+    /// `x - y`.
+    Sub,
+    /// `x * y`.
+    Mul,
+    /// `x / y`.
+    Div,
+    /// `max(x, y)`.
+    Max,
+    /// `min(x, y)`.
+    Min,
+}
+
+/// An elementwise operator applied by a [`Map`] task.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MapOp {
+    /// An operator with one operand.
+    Unary(UnaryOp),
+    /// An operator with two operands.
+    Binary(BinaryOp),
+}
+
+impl Display for UnaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnaryOp::Exp => write!(f, "exp"),
+            UnaryOp::Log => write!(f, "log"),
+            UnaryOp::Neg => write!(f, "neg"),
+            UnaryOp::Relu => write!(f, "relu"),
+            UnaryOp::Gelu => write!(f, "gelu"),
+            UnaryOp::Scale(_) => write!(f, "scale"),
+        }
+    }
+}
+
+impl Display for BinaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BinaryOp::Add => write!(f, "add"),
+            BinaryOp::Sub => write!(f, "sub"),
+            BinaryOp::Mul => write!(f, "mul"),
+            BinaryOp::Div => write!(f, "div"),
+            BinaryOp::Max => write!(f, "max"),
+            BinaryOp::Min => write!(f, "min"),
+        }
+    }
+}
+
+impl Display for MapOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapOp::Unary(op) => write!(f, "{}", op),
+            MapOp::Binary(op) => write!(f, "{}", op),
+        }
+    }
+}
+
+/// [`Map`] is a task that applies an elementwise operator to register
+/// tiles or vectors of the same shape.
+pub struct Map {
+    op: MapOp,
+    inputs: Vec<Rc<Buffer>>,
+    output: Rc<Buffer>,
+    id: usize,
+}
+
+impl Map {
+    /// Create a new `Map` task computing `dst = op(src)`.
+    pub fn unary(op: UnaryOp, src: Rc<Buffer>, dst: Rc<Buffer>) -> ThrillerResult<Self> {
+        Self::new(MapOp::Unary(op), vec![src], dst)
+    }
+
+    /// Create a new `Map` task computing `dst = op(lhs, rhs)`.
+    pub fn binary(
+        op: BinaryOp,
+        lhs: Rc<Buffer>,
+        rhs: Rc<Buffer>,
+        dst: Rc<Buffer>,
+    ) -> ThrillerResult<Self> {
+        Self::new(MapOp::Binary(op), vec![lhs, rhs], dst)
+    }
+
+    fn new(op: MapOp, inputs: Vec<Rc<Buffer>>, output: Rc<Buffer>) -> ThrillerResult<Self> {
+        // Operands are register tiles or register vectors of the same
+        // typing, dimensions and element type.
+        for buf in inputs.iter().chain(std::iter::once(&output)) {
+            if !matches!(buf.get_typing(), BufType::RegTile | BufType::RegVec)
+                || buf.get_typing() != output.get_typing()
+            {
+                return Err(ThrillerError::InvalidBufType);
+            }
+
+            if buf.get_shape().get_dims() != output.get_shape().get_dims() {
+                return Err(ThrillerError::ShapeMismatch);
+            }
+
+            if buf.get_dtype() != output.get_dtype() {
+                return Err(ThrillerError::DataTypeMismatch);
+            }
+        }
+
+        Ok(Map {
+            op,
+            inputs,
+            output,
+            id: next_id(),
+        })
+    }
+
+    /// Get the elementwise operator.
+    pub fn get_op(&self) -> MapOp {
+        self.op
+    }
+}
+
+impl Task for Map {
+    fn emit(&self) -> ThrillerResult<String> {
+        let mut operands = self
+            .inputs
+            .iter()
+            .chain(std::iter::once(&self.output))
+            .map(|buf| buf.get_name().clone())
+            .collect::<Vec<_>>();
+
+        if let MapOp::Unary(UnaryOp::Scale(factor)) = self.op {
+            operands.push(format!("{:?}", factor));
+        }
+
+        Ok(format!(
+            "compute::{op}({operands});\n",
+            op = self.op,
+            operands = operands.join(", ")
+        ))
+    }
+
+    fn get_name(&self) -> String {
+        format!("Map_{}", self.id)
     }
 }
@@ -1,5 +1,9 @@
+mod broadcast;
 mod gemm;
 mod map;
+mod reduce;
 
-pub use gemm::Gemm;
-pub use map::Convert;
+pub use broadcast::Broadcast;
+pub use gemm::{BatchedGemm, Gemm, GroupedGemm};
+pub use map::{BinaryOp, Convert, Map, MapOp, UnaryOp};
+pub use reduce::{Combiner, Reduce};
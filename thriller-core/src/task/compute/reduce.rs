@@ -0,0 +1,91 @@
+use std::fmt::Display;
+use std::rc::Rc;
+
+use crate::{next_id, BufType, Buffer, Dimension, Task, ThrillerError, ThrillerResult};
+
+/// Combiners of a [`Reduce`] task.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Combiner {
+    /// Sum of the elements.
+    Sum,
+    /// Maximum of the elements.
+    Max,
+    /// Minimum of the elements.
+    Min,
+}
+
+impl Display for Combiner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Combiner::Sum => write!(f, "sum"),
+            Combiner::Max => write!(f, "max"),
+            Combiner::Min => write!(f, "min"),
+        }
+    }
+}
+
+/// [`Reduce`] is a task that reduces a 2-dimensional register tile along
+/// one axis into a register vector.
+///
+/// Reducing along axis 1 combines the columns of each row, e.g. the row
+/// maximum in softmax, reducing along axis 0 combines the rows of each
+/// column.
+pub struct Reduce {
+    src: Rc<Buffer>,
+    dst: Rc<Buffer>,
+    axis: usize,
+    combiner: Combiner,
+    id: usize,
+}
+
+impl Reduce {
+    /// Create a new `Reduce` task.
+    pub fn new(
+        src: Rc<Buffer>,
+        dst: Rc<Buffer>,
+        axis: usize,
+        combiner: Combiner,
+    ) -> ThrillerResult<Self> {
+        if *src.get_typing() != BufType::RegTile || *dst.get_typing() != BufType::RegVec {
+            return Err(ThrillerError::InvalidBufType);
+        }
+
+        let src_dims = src.get_shape().get_dims().slice();
+        if src_dims.len() != 2 || axis > 1 {
+            return Err(ThrillerError::ShapeMismatch);
+        }
+
+        // One element per row when reducing along axis 1, per column otherwise.
+        if dst.get_shape().get_dims().slice() != [src_dims[1 - axis]] {
+            return Err(ThrillerError::ShapeMismatch);
+        }
+
+        if src.get_dtype() != dst.get_dtype() {
+            return Err(ThrillerError::DataTypeMismatch);
+        }
+
+        Ok(Reduce {
+            src,
+            dst,
+            axis,
+            combiner,
+            id: next_id(),
+        })
+    }
+}
+
+impl Task for Reduce {
+    fn emit(&self) -> ThrillerResult<String> {
+        Ok(format!(
+            "compute::{direction}_{combiner}({src}, {dst});\n",
+            direction = if self.axis == 1 { "row" } else { "col" },
+            combiner = self.combiner,
+            src = self.src.get_name(),
+            dst = self.dst.get_name()
+        ))
+    }
+
+    fn get_name(&self) -> String {
+        format!("Reduce_{}", self.id)
+    }
+}
@@ -1,28 +1,45 @@
-// use std::task::Context;
+use std::rc::Rc;
 
-// use crate::{task::Task, ThrillerResult};
+use crate::{next_id, BufType, Buffer, Task, ThrillerError, ThrillerResult};
 
-// #[allow(dead_code)]
-// pub enum CopyType {
-//     GlobalToShared,
-//     SharedToGlobal,
-//     SharedToRF,
-//     RFToShared,
-// }
+/// [`TileCopy`] is a task that copies a register tile or vector into another
+/// one with the same layout, e.g. to forward a result between two fused loop
+/// bodies.
+pub struct TileCopy {
+    src: Rc<Buffer>,
+    dst: Rc<Buffer>,
+    id: usize,
+}
 
-// /// A task to copy data from one memory to another
-// pub struct CopyTask {
-//     copy_type: CopyType,
-// }
+impl TileCopy {
+    /// Create a new `TileCopy` task.
+    pub fn new(src: Rc<Buffer>, dst: Rc<Buffer>) -> Self {
+        TileCopy {
+            src,
+            dst,
+            id: next_id(),
+        }
+    }
+}
 
-// impl Task for CopyTask {
-//     fn emit(&self, ctx: Context) -> ThrillerResult<String> {
-//         match self.copy_type {
-//             // TODO: Implement emit for each CopyType
-//             CopyType::GlobalToShared => Ok(format!("copy_tensor_g2s({}, {}, {})", "a", "b", "c")),
-//             CopyType::SharedToGlobal => Ok(format!("copy_tensor_s2g({}, {}, {})", "a", "b", "c")),
-//             CopyType::SharedToRF => Ok(format!("copy_tensor_s2rf({}, {}, {})", "a", "b", "c")),
-//             CopyType::RFToShared => Ok(format!("copy_tensor_rf2s({}, {}, {})", "a", "b", "c")),
-//         }
-//     }
-// }
+impl Task for TileCopy {
+    fn emit(&self) -> ThrillerResult<String> {
+        match (self.src.get_typing(), self.dst.get_typing()) {
+            (BufType::RegTile, BufType::RegTile) => Ok(format!(
+                "copy_tile_r2r({src}, {dst});\n",
+                src = self.src.get_name(),
+                dst = self.dst.get_name()
+            )),
+            (BufType::RegVec, BufType::RegVec) => Ok(format!(
+                "copy_vec_r2r({src}, {dst});\n",
+                src = self.src.get_name(),
+                dst = self.dst.get_name()
+            )),
+            _ => Err(ThrillerError::InvalidBufType),
+        }
+    }
+
+    fn get_name(&self) -> String {
+        format!("TileCopy_{}", self.id)
+    }
+}
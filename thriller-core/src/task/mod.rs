@@ -1,9 +1,15 @@
-use crate::ThrillerResult;
+use std::rc::Rc;
+
+use crate::{AccessMap, Diagnostic, MemoryAccess, ThrillerResult};
 
 mod compute;
 mod copy;
 
-pub use compute::{Convert, Gemm};
+pub use compute::{
+    BatchedGemm, BinaryOp, Broadcast, Combiner, Convert, Gemm, GroupedGemm, Map, MapOp, Reduce,
+    UnaryOp,
+};
+pub use copy::TileCopy;
 
 /// A trait to represent a task.
 pub trait Task {
@@ -12,4 +18,26 @@ pub trait Task {
 
     /// Get the name of the task.
     fn get_name(&self) -> String;
+
+    /// Get the [`AccessMap`] of the task, if it indexes its operands
+    /// with the iteration variables of enclosing loops.
+    fn get_access_map(&self) -> Option<&Rc<AccessMap>> {
+        None
+    }
+
+    /// Replace the [`AccessMap`] of the task, used by loop transformations.
+    fn set_access_map(&mut self, _access_map: Rc<AccessMap>) {}
+
+    /// The accesses of the task to its operands through its [`AccessMap`],
+    /// in program order, used by [`crate::DependenceAnalysis`].
+    fn get_accesses(&self) -> Vec<MemoryAccess> {
+        vec![]
+    }
+
+    /// Check the shapes and types of the operands, used by
+    /// [`crate::ShapeValidation`]. Tasks validating their operands on
+    /// creation have nothing left to check.
+    fn validate(&self) -> Vec<Diagnostic> {
+        vec![]
+    }
 }
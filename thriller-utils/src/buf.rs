@@ -1,36 +1,97 @@
-use thriller_core::{BufType, Buffer, Layout};
+use thriller_core::{BufType, Buffer, DataType, Layout, Swizzle};
 
 /// Buffer builder.
+///
+/// Buffers are created with the default [`DataType`], use [`Buffer::new`]
+/// for other element types.
 pub struct BufBuilder();
 
 impl BufBuilder {
     /// Create a new Row Major Global Tile buffer with the given name and dimension.
     pub fn row_major_global_tile(name: &str, dim: &[usize]) -> Buffer {
-        Buffer::new(name, BufType::GlobalTile, dim, Layout::RowMajor)
+        Buffer::new(
+            name,
+            BufType::GlobalTile,
+            dim,
+            Layout::RowMajor,
+            DataType::default(),
+        )
     }
 
     /// Create a new Column Major Global Tile buffer with the given name and dimension.
     pub fn col_major_global_tile(name: &str, dim: &[usize]) -> Buffer {
-        Buffer::new(name, BufType::GlobalTile, dim, Layout::ColumnMajor)
+        Buffer::new(
+            name,
+            BufType::GlobalTile,
+            dim,
+            Layout::ColumnMajor,
+            DataType::default(),
+        )
     }
 
     /// Create a new Row Major Shared Tile buffer with the given name and dimension.
     pub fn row_major_shared_tile(name: &str, dim: &[usize]) -> Buffer {
-        Buffer::new(name, BufType::SharedTile, dim, Layout::RowMajor)
+        Buffer::new(
+            name,
+            BufType::SharedTile,
+            dim,
+            Layout::RowMajor,
+            DataType::default(),
+        )
     }
 
     /// Create a new Column Major Shared Tile buffer with the given name and dimension.
     pub fn col_major_shared_tile(name: &str, dim: &[usize]) -> Buffer {
-        Buffer::new(name, BufType::SharedTile, dim, Layout::ColumnMajor)
+        Buffer::new(
+            name,
+            BufType::SharedTile,
+            dim,
+            Layout::ColumnMajor,
+            DataType::default(),
+        )
+    }
+
+    /// Create a new Swizzled Shared Tile buffer with the given name, dimension and swizzle function.
+    pub fn swizzled_shared_tile(name: &str, dim: &[usize], swizzle: Swizzle) -> Buffer {
+        Buffer::new(
+            name,
+            BufType::SharedTile,
+            dim,
+            Layout::Swizzle(swizzle),
+            DataType::default(),
+        )
     }
 
     /// Create a new Row Major Register Tile buffer with the given name and dimension.
     pub fn row_major_reg_tile(name: &str, dim: &[usize]) -> Buffer {
-        Buffer::new(name, BufType::RegTile, dim, Layout::RowMajor)
+        Buffer::new(
+            name,
+            BufType::RegTile,
+            dim,
+            Layout::RowMajor,
+            DataType::default(),
+        )
     }
 
     /// Create a new Column Major Register Tile buffer with the given name and dimension.
     pub fn col_major_reg_tile(name: &str, dim: &[usize]) -> Buffer {
-        Buffer::new(name, BufType::RegTile, dim, Layout::ColumnMajor)
+        Buffer::new(
+            name,
+            BufType::RegTile,
+            dim,
+            Layout::ColumnMajor,
+            DataType::default(),
+        )
+    }
+
+    /// Create a new Register Vector buffer with the given name and length.
+    pub fn reg_vec(name: &str, len: usize) -> Buffer {
+        Buffer::new(
+            name,
+            BufType::RegVec,
+            &[len],
+            Layout::RowMajor,
+            DataType::default(),
+        )
     }
 }
@@ -5,5 +5,7 @@
 #![deny(warnings)]
 
 mod buf;
+mod softmax;
 
 pub use buf::BufBuilder;
+pub use softmax::OnlineSoftmax;
@@ -0,0 +1,180 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use thriller_core::{
+    BinaryOp, Broadcast, BufType, Buffer, Combiner, Dimension, Layout, Map, Reduce, Task,
+    ThrillerEdge, ThrillerGraph, ThrillerNode, ThrillerNodeInner, ThrillerResult, TileCopy,
+    UnaryOp,
+};
+
+type NodeRef = Rc<RefCell<ThrillerNode>>;
+
+/// Online softmax builder of FlashAttention-v2.
+///
+/// For the scores `S` of one key/value block, it expands to the nodes
+/// updating the running row maximum `m` and row sum `l`, and rescaling the
+/// output accumulator `O`:
+///
+/// ```text
+/// m' = max(m, rowmax(S))
+/// P  = exp(S - m')
+/// s  = exp(m - m')
+/// l  = l * s + rowsum(P)
+/// O  = O * s
+/// m  = m'
+/// ```
+///
+/// The probabilities `P` are then multiplied with the `V` block and
+/// accumulated into `O`, and after the last block `O` is divided by `l`,
+/// see [`OnlineSoftmax::normalize`].
+pub struct OnlineSoftmax {
+    buffers: Vec<NodeRef>,
+    ops: Vec<NodeRef>,
+    probs: Rc<Buffer>,
+}
+
+impl OnlineSoftmax {
+    /// Create the online softmax of the scores `scores` (`[M, N]`) with the
+    /// running maximum `max` and sum `sum` (`[M]`) and the accumulator `acc`
+    /// (`[M, D]`).
+    ///
+    /// Temporary buffers are named after `scores` and share its element type.
+    pub fn new(
+        scores: Rc<Buffer>,
+        max: Rc<Buffer>,
+        sum: Rc<Buffer>,
+        acc: Rc<Buffer>,
+    ) -> ThrillerResult<Self> {
+        let name = scores.get_name();
+        let dims = scores.get_shape().get_dims().slice().to_vec();
+        let rows = dims.first().copied().unwrap_or(0);
+
+        let temp = |suffix: &str, typing: BufType, dims: &[usize]| {
+            Rc::new(Buffer::new(
+                &format!("{}_{}", name, suffix),
+                typing,
+                dims,
+                Layout::RowMajor,
+                scores.get_dtype(),
+            ))
+        };
+
+        let block_max = temp("max", BufType::RegVec, &[rows]);
+        let new_max = temp("new_max", BufType::RegVec, &[rows]);
+        let scale = temp("scale", BufType::RegVec, &[rows]);
+        let block_sum = temp("sum", BufType::RegVec, &[rows]);
+        let probs = temp("probs", BufType::RegTile, &dims);
+
+        let ops: Vec<Box<dyn Task>> = vec![
+            Box::new(Reduce::new(
+                scores.clone(),
+                block_max.clone(),
+                1,
+                Combiner::Max,
+            )?),
+            Box::new(Map::binary(
+                BinaryOp::Max,
+                max.clone(),
+                block_max.clone(),
+                new_max.clone(),
+            )?),
+            Box::new(Map::binary(
+                BinaryOp::Sub,
+                max.clone(),
+                new_max.clone(),
+                scale.clone(),
+            )?),
+            Box::new(Map::unary(UnaryOp::Exp, scale.clone(), scale.clone())?),
+            Box::new(Broadcast::new(
+                BinaryOp::Sub,
+                scores.clone(),
+                new_max.clone(),
+                probs.clone(),
+                1,
+            )?),
+            Box::new(Map::unary(UnaryOp::Exp, probs.clone(), probs.clone())?),
+            Box::new(Reduce::new(
+                probs.clone(),
+                block_sum.clone(),
+                1,
+                Combiner::Sum,
+            )?),
+            Box::new(Map::binary(
+                BinaryOp::Mul,
+                sum.clone(),
+                scale.clone(),
+                sum.clone(),
+            )?),
+            Box::new(Map::binary(
+                BinaryOp::Add,
+                sum.clone(),
+                block_sum.clone(),
+                sum.clone(),
+            )?),
+            Box::new(Broadcast::new(
+                BinaryOp::Mul,
+                acc.clone(),
+                scale.clone(),
+                acc.clone(),
+                1,
+            )?),
+            Box::new(TileCopy::new(new_max.clone(), max.clone())),
+        ];
+
+        let buffers = [block_max, new_max, scale, block_sum, probs.clone()]
+            .into_iter()
+            .map(|buf| {
+                Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::Buffer(
+                    buf,
+                ))))
+            })
+            .collect();
+        let ops = ops
+            .into_iter()
+            .map(|op| Rc::new(RefCell::new(ThrillerNode::new(ThrillerNodeInner::Op(op)))))
+            .collect();
+
+        Ok(OnlineSoftmax {
+            buffers,
+            ops,
+            probs,
+        })
+    }
+
+    /// Divide the accumulator `acc` by the running sum `sum` once all
+    /// blocks are processed.
+    pub fn normalize(acc: Rc<Buffer>, sum: Rc<Buffer>) -> ThrillerResult<NodeRef> {
+        let div = Broadcast::new(BinaryOp::Div, acc.clone(), sum, acc, 1)?;
+        Ok(Rc::new(RefCell::new(ThrillerNode::new(
+            ThrillerNodeInner::op(div),
+        ))))
+    }
+
+    /// Get the probabilities `P` of the block.
+    pub fn get_probs(&self) -> &Rc<Buffer> {
+        &self.probs
+    }
+
+    /// Get the temporary buffer nodes.
+    pub fn get_buffers(&self) -> &Vec<NodeRef> {
+        &self.buffers
+    }
+
+    /// Get the operation nodes in program order.
+    pub fn get_ops(&self) -> &Vec<NodeRef> {
+        &self.ops
+    }
+
+    /// Add the nodes into `graph`, with edges ordering the operations as
+    /// they update the running statistics in place.
+    pub fn build(&self, graph: &mut ThrillerGraph) {
+        graph.add_nodes(self.buffers.clone());
+        graph.add_nodes(self.ops.clone());
+        graph.add_edges(
+            self.ops
+                .windows(2)
+                .map(|pair| Rc::new(ThrillerEdge::new(pair[0].clone(), pair[1].clone())))
+                .collect(),
+        );
+    }
+}